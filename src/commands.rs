@@ -1,7 +1,10 @@
 use crate::{FerrisError, Lavalink};
 use lavalink_rs::prelude::{LavalinkClient, PlayerContext};
 use serenity::{
-    all::{CommandDataOption, CommandDataOptionValue, CommandInteraction},
+    all::{
+        CommandDataOption, CommandDataOptionValue, CommandInteraction, ResolvedOption,
+        ResolvedValue,
+    },
     client::Context,
     model::id::GuildId,
 };
@@ -9,16 +12,34 @@ use songbird::Songbird;
 use std::sync::Arc;
 
 // Modules
+pub mod always_on;
+pub mod announce;
+pub mod eq;
+pub mod filter;
 pub mod goto;
 pub mod help;
+pub mod history;
 pub mod join;
 pub mod leave;
 pub mod loop_track;
+pub mod move_track;
 pub mod np;
+pub mod pause;
 pub mod play;
 pub mod queue;
+pub mod remove;
+pub mod replay;
+pub mod reverse;
+pub mod search;
+pub mod settings;
+pub mod shuffle;
 pub mod skip;
+pub mod skip_to;
+pub mod sort;
 pub mod stop;
+pub mod swap;
+pub mod timescale;
+pub mod volume;
 
 pub async fn get_songbird_manager(ctx: &Context) -> Arc<Songbird> {
     songbird::get(ctx)
@@ -34,6 +55,31 @@ fn get_args(args: &[CommandDataOption]) -> Result<String, FerrisError> {
     }
 }
 
+/// Find value of an option by its name
+fn get_option<'a, 'b>(
+    options: &'b [ResolvedOption<'a>],
+    name: &str,
+) -> Option<&'b ResolvedValue<'a>> {
+    options
+        .iter()
+        .find(|option| option.name == name)
+        .map(|option| &option.value)
+}
+
+/// Get name and options of the subcommand that was invoked
+fn get_subcommand<'a, 'b>(
+    options: &'b [ResolvedOption<'a>],
+) -> Result<(&'a str, &'b [ResolvedOption<'a>]), FerrisError> {
+    match options.first() {
+        Some(ResolvedOption {
+            name,
+            value: ResolvedValue::SubCommand(sub_options),
+            ..
+        }) => Ok((name, sub_options)),
+        _ => Err(FerrisError::MissingArguments),
+    }
+}
+
 pub async fn get_command_context<'a>(
     ctx: &'a Context,
     interaction: &'a CommandInteraction,
@@ -0,0 +1,32 @@
+use super::get_command_context;
+use crate::{FerrisResponse, Response, idle::update_idle, session::get_sessions};
+use serenity::{all::CommandInteraction, client::Context};
+
+/// Admin command for toggling whether Ferris stays on the voice channel even when alone or idle
+pub async fn always_on(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+
+    // Toggle the setting
+    let settings = {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        let settings = &mut sessions.entry(guild_id).or_default().settings;
+        settings.always_on = !settings.always_on;
+        settings.clone()
+    };
+
+    // Start counting idle time again if Ferris is no longer kept on
+    update_idle(&lava_client, guild_id).await;
+
+    // Respond in Discord
+    let description = if settings.always_on {
+        "Staying on voice channel 24/7".to_string()
+    } else {
+        format!(
+            "Leaving voice channel after {} minutes alone or idle",
+            settings.idle_timeout.as_secs() / 60
+        )
+    };
+    Ok(Response::new().description(&description).build())
+}
@@ -0,0 +1,37 @@
+use super::{get_command_context, get_option, get_subcommand};
+use crate::{
+    FerrisError, FerrisResponse, Response,
+    session::{Announcements, get_sessions},
+};
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+/// Admin command for choosing where announcements are posted
+pub async fn announce(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+    let options = interaction.data.options();
+    let (subcommand, sub_options) = get_subcommand(&options)?;
+    let announcements = match (subcommand, get_option(sub_options, "channel")) {
+        ("channel", Some(ResolvedValue::Channel(channel))) => Announcements::Channel(channel.id),
+        ("channel", _) => Announcements::SessionChannel,
+        ("off", _) => Announcements::Off,
+        _ => Err(FerrisError::MissingArguments)?,
+    };
+
+    // Save the setting
+    get_sessions(&lava_client)?
+        .write()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .settings
+        .announcements = announcements;
+
+    // Respond in Discord
+    Ok(Response::new()
+        .description(&announcements.to_string())
+        .build())
+}
@@ -0,0 +1,94 @@
+use super::{get_command_context, get_option, get_subcommand};
+use crate::{
+    FerrisError, FerrisResponse, MAX_EQ_PROFILES, Response, filters::render_equalizer,
+    session::get_sessions,
+};
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+pub async fn eq(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, player, _) = get_command_context(ctx, interaction).await?;
+    let options = interaction.data.options();
+    let (subcommand, sub_options) = get_subcommand(&options)?;
+
+    // Profiles are settings of the guild, but changing bands needs a player
+    if player.is_none() && matches!(subcommand, "set" | "reset") {
+        Err(FerrisError::NotOnCallError)?
+    }
+    let name = match get_option(sub_options, "name") {
+        Some(ResolvedValue::String(name)) => Some(name.to_string()),
+        _ => None,
+    };
+
+    let (title, equalizer, filters) = {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        let session = sessions.entry(guild_id).or_default();
+
+        let title = match (subcommand, name) {
+            ("set", _) => {
+                let (Some(ResolvedValue::Integer(band)), Some(ResolvedValue::Number(gain))) = (
+                    get_option(sub_options, "band"),
+                    get_option(sub_options, "gain"),
+                ) else {
+                    Err(FerrisError::MissingArguments)?
+                };
+                let band = usize::try_from(*band).map_err(|_| FerrisError::MissingArguments)?;
+                *session
+                    .equalizer
+                    .get_mut(band)
+                    .ok_or(FerrisError::MissingArguments)? = *gain;
+                format!("Set band {band} to {gain:+.2}")
+            }
+            ("reset", _) => {
+                session.equalizer = Default::default();
+                "Reset equalizer".to_string()
+            }
+            ("save", Some(name)) => {
+                let profiles = &mut session.settings.eq_profiles;
+                if !profiles.contains_key(&name) && profiles.len() >= MAX_EQ_PROFILES {
+                    Err(FerrisError::ProfileLimitError)?
+                }
+                profiles.insert(name.clone(), session.equalizer);
+                format!("Saved equalizer profile {name}")
+            }
+            ("load", Some(name)) => {
+                session.equalizer = *session
+                    .settings
+                    .eq_profiles
+                    .get(&name)
+                    .ok_or(FerrisError::ProfileNotFoundError)?;
+                format!("Loaded equalizer profile {name}")
+            }
+            ("list", _) => {
+                let profiles = &session.settings.eq_profiles;
+                if profiles.is_empty() {
+                    Err(FerrisError::ProfileNotFoundError)?
+                }
+                let names: Vec<&str> = profiles.keys().map(String::as_str).collect();
+
+                // Listing doesn't change the equalizer so respond early
+                return Ok(Response::new()
+                    .title("Equalizer profiles")
+                    .description(&names.join("\n"))
+                    .build());
+            }
+            _ => Err(FerrisError::MissingArguments)?,
+        };
+        (title, session.equalizer, session.lavalink_filters())
+    };
+
+    // Send command to Lavalink, loaded profile is applied when Ferris joins if not on a voice channel
+    if let Some(player) = player {
+        player.set_filters(filters).await?;
+    }
+
+    // Respond in Discord
+    Ok(Response::new()
+        .title(&title)
+        .description(&format!("```\n{}```", render_equalizer(&equalizer)))
+        .build())
+}
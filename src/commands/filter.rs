@@ -0,0 +1,51 @@
+use super::{get_command_context, get_option, get_subcommand};
+use crate::{FerrisError, FerrisResponse, Response, filters::FilterPreset, session::get_sessions};
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+pub async fn filter(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
+    else {
+        Err(FerrisError::LavalinkError)?
+    };
+    let options = interaction.data.options();
+    let (subcommand, sub_options) = get_subcommand(&options)?;
+    let preset = match get_option(sub_options, "preset") {
+        Some(ResolvedValue::String(name)) => FilterPreset::from_name(name),
+        _ => None,
+    };
+
+    let (description, filters) = {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        let session = sessions.entry(guild_id).or_default();
+
+        let description = match (subcommand, preset) {
+            ("add", Some(preset)) => {
+                // Move preset on top of the stack if it was already active
+                session.filters.retain(|active| *active != preset);
+                session.filters.push(preset);
+                format!("Added {preset} filter")
+            }
+            ("remove", Some(preset)) => {
+                session.filters.retain(|active| *active != preset);
+                format!("Removed {preset} filter")
+            }
+            ("reset", _) => {
+                session.filters.clear();
+                "Removed all filters".to_string()
+            }
+            _ => Err(FerrisError::MissingArguments)?,
+        };
+        (description, session.lavalink_filters())
+    };
+
+    // Send command to Lavalink
+    player.set_filters(filters).await?;
+
+    // Respond in Discord
+    Ok(Response::new().description(&description).build())
+}
@@ -24,7 +24,7 @@ pub async fn goto(ctx: &Context, interaction: &CommandInteraction) -> FerrisResp
         return Err(FerrisError::QueueEmptyError)?;
     }
 
-    // Send command to Lavalink
+    // Send command to Lavalink. Position is in track time so speed filters don't affect it.
     player.set_position(position).await?;
 
     // Respond in Discord
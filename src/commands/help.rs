@@ -1,25 +1,140 @@
 use crate::{FerrisResponse, Response};
 use serenity::{all::CommandInteraction, prelude::Context};
 
-const HELP_FIELDS: [(&str, &str, bool); 12] = [
-    ("/join", "Joins your current channel", false),
-    ("/leave", "Leaves current channel", false),
+/// Commands by category. Each category is a single field as Discord allows at most 25 fields in an embed
+/// and 1024 characters in a field.
+const HELP_CATEGORIES: [(&str, &[(&str, &str)]); 5] = [
     (
-        "/play {url / query}",
-        "Plays audio from url or plays the first search result",
-        false,
+        "Playback",
+        &[
+            ("/join", "Joins your current channel"),
+            ("/leave", "Leaves current channel"),
+            (
+                "/play {url / query} {position} {engine}",
+                "Plays audio from url or plays the first search result. Position can be next, now or a position in queue. Prefix a query with yt:, ytm:, sc:, sp:, am:, dz: or ym: to pick where to search",
+            ),
+            (
+                "/search {query} {engine}",
+                "Shows the top search results and queues the one you pick. Queries take the same prefixes as /play",
+            ),
+            ("/stop", "Stops current song and clears queue"),
+            ("/replay", "Restarts current track"),
+            ("/help", "Shows this message"),
+            (
+                "/loop {off / track / queue}",
+                "Loops current track or the whole queue, or stops looping",
+            ),
+            ("/np", "Shows current song and its progress"),
+            ("/pause", "Pauses current song"),
+            ("/resume", "Resumes current song"),
+            ("/playpause", "Pauses or resumes current song"),
+            ("/goto {M:S}", "Goes to specified point in the track"),
+        ],
     ),
-    ("/stop", "Stops current song and clears queue", false),
-    ("/queue", "Shows audio in queue", false),
-    ("/skip", "Skips to next track", false),
-    ("/help", "Shows this message", false),
     (
-        "/loop",
-        "Enable or disable looping for current track",
-        false,
+        "Queue",
+        &[
+            ("/queue", "Shows audio in queue"),
+            (
+                "/skip",
+                "Skips to next track, or votes to skip if the server requires votes",
+            ),
+            (
+                "/skipto {position} {keep}",
+                "Skips to a position in queue, keep moves skipped tracks to the end of queue",
+            ),
+            ("/previous", "Plays previous track again"),
+            ("/history", "Shows recently played tracks"),
+            (
+                "/remove {position / range}",
+                "Removes tracks from queue, for example 3 or 2-5",
+            ),
+            ("/move {from} {to}", "Moves a track to another position"),
+            ("/swap {a} {b}", "Swaps positions of two tracks"),
+            (
+                "/shuffle {mode}",
+                "Shuffles queue, mode keeps shuffling new tracks until turned off",
+            ),
+            ("/reverse", "Reverses queue"),
+            (
+                "/sort {title / duration / requester / source}",
+                "Sorts queue",
+            ),
+        ],
+    ),
+    (
+        "Audio",
+        &[
+            ("/volume {0-1000}", "Sets volume or shows current volume"),
+            (
+                "/filter add / remove {preset}",
+                "Stacks or removes audio filters like bassboost, nightcore, vaporwave, 8d and karaoke",
+            ),
+            ("/filter reset", "Removes all audio filters"),
+            ("/eq set {band} {gain}", "Sets gain of an equalizer band"),
+            ("/eq reset", "Resets all equalizer bands"),
+            (
+                "/eq save / load / list {name}",
+                "Saves, loads or lists equalizer profiles of the server",
+            ),
+            ("/speed {multiplier}", "Changes playback speed"),
+            ("/pitch {multiplier}", "Changes pitch"),
+        ],
+    ),
+    (
+        "Settings (admins only)",
+        &[
+            (
+                "/settings volume {default} {max}",
+                "Sets default and maximum volume of the server",
+            ),
+            (
+                "/settings idle {minutes}",
+                "Sets how long Ferris stays on voice channel when alone or nothing is playing",
+            ),
+            (
+                "/settings voteskip {enabled} {ratio}",
+                "Sets whether skipping needs votes and what share of listeners must vote",
+            ),
+            (
+                "/settings dj {role}",
+                "Lets only members with the role control the player, others can play tracks and skip or remove their own",
+            ),
+            (
+                "/settings fairqueue {enabled}",
+                "Makes requesters take turns in queue so that a long playlist doesn't hold up others",
+            ),
+            (
+                "/settings limits {user_tracks} {queue_length} {track_minutes} {streams}",
+                "Limits tracks per member, queue length, track length and live streams, 0 removes a limit",
+            ),
+            (
+                "/settings source {engine}",
+                "Sets where tracks are searched from when no engine or prefix is given",
+            ),
+            (
+                "/settings fallback {sources}",
+                "Sets sources /play tries in order when a search finds nothing, like youtubemusic, soundcloud or none",
+            ),
+        ],
     ),
-    ("/np", "Shows current song and its progress", false),
-    ("/goto {M:S}", "Goes to specified point in the track", false),
+    (
+        "Server (admins only)",
+        &[
+            (
+                "/247",
+                "Toggles staying on voice channel even when alone or nothing is playing",
+            ),
+            (
+                "/announce channel {channel}",
+                "Posts now playing and other messages to a channel, or where Ferris was summoned from if no channel is given",
+            ),
+            ("/announce off", "Stops posting messages"),
+        ],
+    ),
+];
+
+const HELP_FIELDS: [(&str, &str, bool); 2] = [
     (
         "Issues?",
         "If the bot has an issue or doesn't work you can try **stop** or **leave** commands to reset the bot. You can also check known issues at [DJ Ferris repository](https://github.com/tumrin/dj_ferris/issues)",
@@ -29,10 +144,26 @@ const HELP_FIELDS: [(&str, &str, bool); 12] = [
 ];
 
 pub async fn help(_ctx: &Context, _interaction: &CommandInteraction) -> FerrisResponse {
+    let categories: Vec<(&str, String)> = HELP_CATEGORIES
+        .iter()
+        .map(|(category, commands)| {
+            let lines: Vec<String> = commands
+                .iter()
+                .map(|(command, description)| format!("**{command}** {description}"))
+                .collect();
+            (*category, lines.join("\n"))
+        })
+        .collect();
+    let fields = categories
+        .iter()
+        .map(|(category, commands)| (*category, commands.as_str(), false))
+        .chain(HELP_FIELDS)
+        .collect();
+
     // Respond in Discord
     Ok(Response::new()
         .title("Help")
-        .fields(HELP_FIELDS.into())
+        .fields(fields)
         .description("Below is a list of commands")
         .build())
 }
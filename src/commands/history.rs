@@ -0,0 +1,81 @@
+use super::{get_command_context, loop_track::remove_loop_copy};
+use crate::{
+    FerrisError, FerrisResponse, MAX_DESCRIPTION_SIZE, Response, requested_by,
+    session::get_sessions,
+};
+use serenity::{all::CommandInteraction, client::Context};
+use std::fmt::Write;
+
+pub async fn history(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+    let history = get_sessions(&lava_client)?
+        .read()
+        .await
+        .get(&guild_id)
+        .map(|session| session.history.clone())
+        .unwrap_or_default();
+
+    if history.is_empty() {
+        Err(FerrisError::HistoryEmptyError)?;
+    }
+
+    // Construct history, newest first
+    let mut history_string = String::new();
+    for (index, track) in history.iter().rev().enumerate() {
+        let (title, uri) = (
+            &track.info.title,
+            track.info.uri.as_deref().unwrap_or("Unknown"),
+        );
+        let track_string = format!("{}. [{title}]({uri}){}", index + 1, requested_by(track));
+
+        // Prevent writing to more tracks to history string if we are nearing the message limit
+        if (history_string.len() + track_string.len()) > MAX_DESCRIPTION_SIZE {
+            writeln!(history_string, ". . .")?;
+            break;
+        }
+        writeln!(history_string, "{track_string}")?;
+    }
+
+    // Respond in Discord
+    Ok(Response::new()
+        .title("History")
+        .description(&history_string)
+        .build())
+}
+
+pub async fn previous(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
+    else {
+        Err(FerrisError::LavalinkError)?
+    };
+    let current_track = player.get_player().await?.track;
+
+    // Current track plays again after the previous one and isn't recorded to history
+    let (track, loop_mode) = {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        let session = sessions
+            .get_mut(&guild_id)
+            .ok_or(FerrisError::HistoryEmptyError)?;
+        let track = session
+            .history
+            .pop_back()
+            .ok_or(FerrisError::HistoryEmptyError)?;
+        session.going_back = current_track.is_some();
+        (track, session.loop_mode)
+    };
+    if let Some(current_track) = current_track {
+        remove_loop_copy(&player, loop_mode, &current_track).await?;
+        player.get_queue().push_to_front(current_track)?;
+    }
+
+    // Send command to Lavalink
+    player.play_now(&track).await?;
+
+    // Respond in Discord
+    Ok(Response::new()
+        .description(&format!("Went back to {}", track.info.title))
+        .build())
+}
@@ -1,5 +1,5 @@
 use super::get_command_context;
-use crate::{FerrisError, FerrisResponse, Response};
+use crate::{FerrisError, FerrisResponse, Response, idle::update_idle, session::get_sessions};
 use lavalink_rs::model::{ChannelId, player::ConnectionInfo};
 use serenity::{
     all::{CommandInteraction, Mentionable},
@@ -24,7 +24,7 @@ pub async fn join(ctx: &Context, interaction: &CommandInteraction) -> FerrisResp
 
     // Send command to Lavalink
     let connection_info = manager.join_gateway(guild_id, channel_id).await?.0; // Call is discarded since we don't need it
-    lava_client
+    let player = lava_client
         .create_player_context(
             guild_id.get(),
             ConnectionInfo {
@@ -36,6 +36,20 @@ pub async fn join(ctx: &Context, interaction: &CommandInteraction) -> FerrisResp
         )
         .await?;
 
+    // Start a new session for the guild
+    let (default_volume, filters) = {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        let session = sessions.entry(guild_id).or_default();
+        session.text_channel = Some(interaction.channel_id);
+        session.started_by = Some(interaction.user.id);
+        (session.settings.default_volume, session.lavalink_filters())
+    };
+    player.set_volume(default_volume).await?;
+    // Equalizer profile may have been loaded before joining
+    player.set_filters(filters).await?;
+    update_idle(&lava_client, guild_id).await;
+
     // Respond in Discord
     Ok(Response::new()
         .description(&format!("Joined {}", channel_id.mention()))
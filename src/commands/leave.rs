@@ -1,5 +1,5 @@
 use super::get_command_context;
-use crate::{FerrisError, FerrisResponse, Response};
+use crate::{FerrisError, FerrisResponse, Response, session::get_sessions};
 use serenity::all::CommandInteraction;
 use serenity::client::Context;
 
@@ -17,6 +17,9 @@ pub async fn leave(ctx: &Context, interaction: &CommandInteraction) -> FerrisRes
 
     // Send command to lavalink
     lava_client.delete_player(guild_id.get()).await?;
+    if let Some(session) = get_sessions(&lava_client)?.write().await.get_mut(&guild_id) {
+        session.end();
+    }
 
     // Respond in Discord
     Ok(Response::new().description("Left voice channel").build())
@@ -1,34 +1,96 @@
-use super::get_command_context;
-use crate::{FerrisError, FerrisResponse, LoopingTrack, Response};
+use super::{get_args, get_command_context};
+use crate::{
+    FerrisError, FerrisResponse, Response,
+    session::{LoopMode, get_sessions},
+};
+use lavalink_rs::{
+    model::track::TrackData,
+    prelude::{PlayerContext, TrackInQueue},
+};
 use serenity::{all::CommandInteraction, client::Context};
-use tokio::sync::RwLock;
 
 pub async fn loop_track(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
-    let (_, lava_client, Some(player), _) = get_command_context(ctx, interaction).await? else {
+    let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
+    else {
         Err(FerrisError::LavalinkError)?
     };
+    let new_mode = match get_args(&interaction.data.options)?.as_str() {
+        "track" => LoopMode::Track,
+        "queue" => LoopMode::Queue,
+        "off" => LoopMode::Off,
+        _ => Err(FerrisError::MissingArguments)?,
+    };
 
-    let mutex = lava_client.data::<RwLock<Option<LoopingTrack>>>()?;
-    let mut data = mutex.write().await;
-
-    // Try to get current song and return error if this fails
-    let current_song = player
-        .get_player()
-        .await?
-        .track
-        .ok_or(FerrisError::QueueEmptyError)?;
+    let old_mode = std::mem::replace(
+        &mut get_sessions(&lava_client)?
+            .write()
+            .await
+            .entry(guild_id)
+            .or_default()
+            .loop_mode,
+        new_mode,
+    );
 
-    // If we are looping, remove looping track, else add current track as looping track
-    let description = if data.is_some() {
-        (*data) = None;
-        format!("Stop looping {}", &current_song.info.title)
-    } else {
-        let response_string = format!("Start looping {}", &current_song.info.title);
-        (*data) = Some(LoopingTrack(current_song.clone()));
-        player.get_queue().push_to_front(current_song).unwrap_or(());
-        response_string
-    };
+    // Move the copy of current track to where the new mode expects it
+    if let Some(current_song) = player.get_player().await?.track {
+        remove_loop_copy(&player, old_mode, &current_song).await?;
+        add_loop_copy(&player, new_mode, current_song)?;
+    }
 
     // Respond in Discord
-    Ok(Response::new().description(&description).build())
+    Ok(Response::new().description(&new_mode.to_string()).build())
+}
+
+/// Requeue a copy of the track so that it plays again
+///
+/// # Arguments
+///
+/// * `player` - player context of the guild
+/// * `mode` - looping mode which decides where the copy goes
+/// * `track` - track that is currently playing
+///
+pub fn add_loop_copy(
+    player: &PlayerContext,
+    mode: LoopMode,
+    track: TrackData,
+) -> Result<(), FerrisError> {
+    let queue = player.get_queue();
+    match mode {
+        LoopMode::Off => Ok(()),
+        LoopMode::Track => queue.push_to_front(track),
+        LoopMode::Queue => queue.push_to_back(track),
+    }
+    .map_err(|_| FerrisError::LavalinkError)
+}
+
+/// Remove the copy of the track that was requeued by [`add_loop_copy`]
+///
+/// # Arguments
+///
+/// * `player` - player context of the guild
+/// * `mode` - looping mode the copy was added with
+/// * `track` - track that is currently playing
+///
+pub async fn remove_loop_copy(
+    player: &PlayerContext,
+    mode: LoopMode,
+    track: &TrackData,
+) -> Result<(), FerrisError> {
+    let queue = player.get_queue();
+    let tracks = queue
+        .get_queue()
+        .await
+        .map_err(|_| FerrisError::LavalinkError)?;
+    let is_copy = |queued: &TrackInQueue| queued.track.encoded == track.encoded;
+    let index = match mode {
+        LoopMode::Off => None,
+        LoopMode::Track => tracks.front().filter(|queued| is_copy(queued)).map(|_| 0),
+        LoopMode::Queue => tracks.iter().rposition(is_copy),
+    };
+    if let Some(index) = index {
+        queue
+            .remove(index)
+            .map_err(|_| FerrisError::LavalinkError)?;
+    }
+    Ok(())
 }
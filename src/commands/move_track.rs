@@ -0,0 +1,51 @@
+use super::{get_command_context, get_option};
+use crate::{FerrisError, FerrisResponse, Response, get_queue};
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+pub async fn move_track(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+    let options = interaction.data.options();
+    let (Some(ResolvedValue::Integer(from)), Some(ResolvedValue::Integer(to))) =
+        (get_option(&options, "from"), get_option(&options, "to"))
+    else {
+        Err(FerrisError::MissingArguments)?
+    };
+    let queue = get_queue(&lava_client, guild_id).await?;
+    let count = queue.get_count().await?;
+    let (from, to) = (to_index(*from, count)?, to_index(*to, count)?);
+
+    // Send command to Lavalink
+    let track = queue
+        .get_track(from)
+        .await?
+        .ok_or(FerrisError::QueueIndexError)?;
+    queue.remove(from)?;
+    queue.insert(to, track.clone())?;
+
+    // Respond in Discord
+    Ok(Response::new()
+        .description(&format!(
+            "Moved [{}]({}) from {from} to {to}",
+            track.track.info.title,
+            track.track.info.uri.as_deref().unwrap_or("Unknown")
+        ))
+        .build())
+}
+
+/// Convert command argument to a queue index, checking that it's within the queue
+///
+/// # Arguments
+///
+/// * `index` - index given as an argument to the command
+/// * `count` - amount of tracks in queue
+///
+pub fn to_index(index: i64, count: usize) -> Result<usize, FerrisError> {
+    usize::try_from(index)
+        .ok()
+        .filter(|index| *index < count)
+        .ok_or(FerrisError::QueueIndexError)
+}
@@ -1,25 +1,61 @@
 use super::get_command_context;
-use crate::{FerrisError, FerrisResponse, Response, get_progress};
+use crate::{
+    FerrisError, FerrisResponse, Response, filters::playback_speed, get_progress, requested_by,
+    session::get_sessions,
+};
 use serenity::{all::CommandInteraction, client::Context};
+use std::fmt::Write;
 
 pub async fn now_playing(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
     // Init variables
-    let (_, _, Some(player), _) = get_command_context(ctx, interaction).await? else {
+    let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
+    else {
         Err(FerrisError::LavalinkError)?
     };
     let player = player.get_player().await?;
     let current_song = player.track.ok_or(FerrisError::QueueEmptyError)?;
+    let (speed, filter_names): (f64, Vec<String>) = get_sessions(&lava_client)?
+        .read()
+        .await
+        .get(&guild_id)
+        .map(|session| {
+            (
+                playback_speed(&session.lavalink_filters()),
+                session
+                    .filters
+                    .iter()
+                    .map(|preset| preset.to_string())
+                    .collect(),
+            )
+        })
+        .unwrap_or((1.0, Vec::new()));
+
+    let mut description = format!(
+        "{}{}\n {}\n🔊 {}%",
+        current_song.info.title,
+        requested_by(&current_song),
+        get_progress(
+            player.state.position / 1000,
+            current_song.info.length / 1000,
+            player.paused,
+            speed,
+        ),
+        player.volume
+    );
+    if speed != 1.0 {
+        write!(description, "\t⏩ {speed:.2}x")?;
+    }
+    if !filter_names.is_empty() {
+        write!(description, "\n🎛️ {}", filter_names.join(", "))?;
+    }
 
     // Respond in Discord
     Ok(Response::new()
-        .title("Now playing")
-        .description(&format!(
-            "{}\n {}",
-            current_song.info.title,
-            get_progress(
-                player.state.position / 1000,
-                current_song.info.length / 1000,
-            )
-        ))
+        .title(if player.paused {
+            "Paused"
+        } else {
+            "Now playing"
+        })
+        .description(&description)
         .build())
 }
@@ -0,0 +1,48 @@
+use super::get_command_context;
+use crate::{FerrisError, FerrisResponse, Response};
+use serenity::{all::CommandInteraction, client::Context};
+
+pub async fn pause(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    set_pause(ctx, interaction, Some(true)).await
+}
+
+pub async fn resume(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    set_pause(ctx, interaction, Some(false)).await
+}
+
+pub async fn toggle_pause(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    set_pause(ctx, interaction, None).await
+}
+
+/// Pause or resume current track.
+///
+/// # Arguments
+///
+/// * `ctx` - Serenity context
+/// * `interaction` - interaction which invoked the command
+/// * `pause` - whether to pause or resume, `None` toggles the current state
+///
+async fn set_pause(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    pause: Option<bool>,
+) -> FerrisResponse {
+    // Init variables
+    let (_, _, Some(player), _) = get_command_context(ctx, interaction).await? else {
+        Err(FerrisError::LavalinkError)?
+    };
+    let player_data = player.get_player().await?;
+    let track = player_data.track.ok_or(FerrisError::QueueEmptyError)?;
+    let pause = pause.unwrap_or(!player_data.paused);
+
+    // Send command to Lavalink
+    player.set_pause(pause).await?;
+
+    // Respond in Discord
+    let description = if pause {
+        format!("Paused {}", track.info.title)
+    } else {
+        format!("Resumed {}", track.info.title)
+    };
+    Ok(Response::new().description(&description).build())
+}
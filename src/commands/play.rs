@@ -1,80 +1,596 @@
-use super::{get_args, get_command_context, join::join};
-use crate::{FerrisError, FerrisResponse, Response};
+use super::{get_command_context, get_option, join::join, loop_track::remove_loop_copy};
+use crate::{
+    FerrisError, FerrisResponse, Lavalink, LavalinkData, MAX_OPTION_LENGTH, Response, get_queue,
+    get_requester, round_robin_position,
+    session::{GuildSettings, LoopMode, get_sessions, get_settings},
+    set_requester,
+    sources::{SearchSource, pick_source, search_order},
+    truncate,
+};
 use lavalink_rs::{
-    model::track::TrackLoadType,
-    prelude::{SearchEngines, TrackInQueue, TrackLoadData},
+    client::LavalinkClient,
+    model::track::TrackData,
+    prelude::{PlayerContext, TrackInQueue, TrackLoadData},
+};
+use rand::Rng;
+use serenity::{
+    all::{CommandInteraction, ResolvedOption, ResolvedValue},
+    builder::CreateAutocompleteResponse,
+    client::Context,
+    model::id::{GuildId, UserId},
 };
-use serenity::{all::CommandInteraction, client::Context};
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// How long to wait for the member to stop typing before searching for suggestions
+const AUTOCOMPLETE_DEBOUNCE: Duration = Duration::from_millis(300);
+/// How long search results are reused for suggestions
+const SUGGESTION_LIFETIME: Duration = Duration::from_secs(10 * 60);
+const MAX_CACHED_QUERIES: usize = 100;
+const MAX_SUGGESTIONS: usize = 10;
+const MAX_RECENT_SUGGESTIONS: usize = 3;
+/// Shorter queries only get recent requests as suggestions
+const MIN_SUGGESTION_QUERY: usize = 3;
 
 pub async fn play(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
-    // Return error if Join gives some other error then already being on call
-    if let Err(error) = join(ctx, interaction).await {
-        let ferris_error = *error.downcast::<FerrisError>()?;
-        if !matches!(ferris_error, FerrisError::AlreadyOnCallError) {
-            return Err(ferris_error)?;
-        }
-    }
+    // Join unless already on a voice channel
+    join_if_needed(ctx, interaction).await?;
 
     // Init variables
     let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
     else {
         Err(FerrisError::LavalinkError)?
     };
-    let url = get_args(&interaction.data.options)?;
-    let query = if url.starts_with("http") {
-        url.to_string()
-    } else {
-        SearchEngines::YouTube.to_query(&url)?
+    let options = interaction.data.options();
+    let Some(ResolvedValue::String(url)) = get_option(&options, "url") else {
+        Err(FerrisError::MissingArguments)?
     };
-    let query_result = lava_client.load_tracks(guild_id.get(), &query).await?;
-
-    // Queue tracks
-    let mut playlist_info = None;
-    let tracks: Vec<TrackInQueue> = match query_result.data {
-        Some(TrackLoadData::Track(track)) => vec![track.into()],
-        Some(TrackLoadData::Search(results)) => vec![results[0].clone().into()],
-        Some(TrackLoadData::Playlist(playlist)) => {
-            playlist_info = Some(playlist.info);
-            playlist
-                .tracks
-                .into_iter()
-                .map(|track| track.into())
-                .collect()
+    let position = match get_option(&options, "position") {
+        Some(ResolvedValue::String(position)) => {
+            QueuePosition::parse(position).ok_or(FerrisError::QueueIndexError)?
         }
-        _ => Err(FerrisError::TrackNotFoundError)?,
+        _ => QueuePosition::End,
     };
-
-    // Get name and url of the track
-    let (name, url) = match query_result.load_type {
-        TrackLoadType::Playlist => (
-            playlist_info.map_or("Unknown".to_string(), |info| info.name),
+    let (data, fallback) = if url.starts_with("http") {
+        (
+            lava_client.load_tracks(guild_id.get(), url).await?.data,
             None,
-        ),
-        TrackLoadType::Track | TrackLoadType::Search => {
-            let track = tracks[0].clone().track;
-            (track.info.title, track.info.uri)
+        )
+    } else {
+        let settings = get_settings(&lava_client, guild_id).await?;
+        let (source, query) = pick_source(url, chosen_source(&options)?, settings.default_source);
+        let sources = search_order(source, &settings.fallback_sources);
+        let (data, found_on) = search_sources(&lava_client, guild_id, &sources, query).await?;
+        (Some(data), (found_on != source).then_some(found_on))
+    };
+
+    // Get tracks with name and url of the track
+    let (tracks, name, url): (Vec<TrackData>, String, Option<String>) = match data {
+        Some(TrackLoadData::Track(track)) => {
+            let (name, url) = (track.info.title.clone(), track.info.uri.clone());
+            (vec![track], name, url)
+        }
+        Some(TrackLoadData::Search(results)) if !results.is_empty() => {
+            let track = results[0].clone();
+            let (name, url) = (track.info.title.clone(), track.info.uri.clone());
+            (vec![track], name, url)
         }
-        _ => ("Unknown".to_string(), None),
+        Some(TrackLoadData::Playlist(playlist)) => (playlist.tracks, playlist.info.name, None),
+        Some(TrackLoadData::Error(exception)) => {
+            Err(FerrisError::TrackLoadError(exception.message))?
+        }
+        _ => Err(FerrisError::TrackNotFoundError)?,
+    };
+    let name = match url {
+        Some(url) => format!("[{name}]({url})"),
+        None => name,
     };
 
     // Send command to Lavalink
-    let description = if player.get_player().await?.track.is_some() {
-        player.get_queue().append(tracks.clone().into())?;
-        if let Some(url) = url {
-            format!("Added [{name}]({url}) to queue")
-        } else {
-            format!("Added {name} to queue")
+    let mut description = queue_loaded_tracks(
+        &lava_client,
+        guild_id,
+        &player,
+        interaction.user.id,
+        tracks,
+        &name,
+        position,
+    )
+    .await?;
+    if let Some(source) = fallback {
+        write!(description, "\nFound on {source}")?;
+    }
+
+    // Respond in Discord
+    Ok(Response::new().description(&description).build())
+}
+
+/// Search sources in order until one of them finds something. If none do, the error of the last failed
+/// load is returned so that members can see why Lavalink couldn't load the track.
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `sources` - sources to search from in order
+/// * `query` - search query without a source prefix
+///
+async fn search_sources(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    sources: &[SearchSource],
+    query: &str,
+) -> Result<(TrackLoadData, SearchSource), Box<dyn Error + Sync + Send>> {
+    let mut error = FerrisError::TrackNotFoundError;
+    for source in sources {
+        match lava_client
+            .load_tracks(guild_id.get(), &source.engine().to_query(query)?)
+            .await
+            .map(|result| result.data)
+        {
+            Ok(Some(TrackLoadData::Error(exception))) => {
+                error = FerrisError::TrackLoadError(exception.message)
+            }
+            Ok(Some(TrackLoadData::Search(results))) if results.is_empty() => (),
+            Ok(Some(data)) => return Ok((data, *source)),
+            Ok(None) => (),
+            // Source may need a plugin the Lavalink server doesn't have
+            Err(_) => {
+                if matches!(error, FerrisError::TrackNotFoundError) {
+                    error = FerrisError::LavalinkError
+                }
+            }
         }
+    }
+    Err(error)?
+}
+
+/// Suggest the member's own recent requests and search results while they type a query
+pub async fn autocomplete(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+) -> Result<CreateAutocompleteResponse, Box<dyn Error + Sync + Send>> {
+    // Init variables
+    let guild_id = interaction.guild_id.ok_or(FerrisError::GuildError)?;
+    let lava_client = ctx
+        .data
+        .read()
+        .await
+        .get::<Lavalink>()
+        .ok_or(FerrisError::LavalinkError)?
+        .clone();
+    let query = interaction
+        .data
+        .autocomplete()
+        .map(|option| option.value.trim().to_string())
+        .unwrap_or_default();
+    let mut response = CreateAutocompleteResponse::new();
+
+    // Urls are played as they are
+    if query.starts_with("http") {
+        return Ok(response);
+    }
+
+    // Search from the source picked by a prefix or the engine option
+    let default_source = get_settings(&lava_client, guild_id).await?.default_source;
+    let (source, query) = pick_source(
+        &query,
+        chosen_source(&interaction.data.options())?,
+        default_source,
+    );
+
+    // Recently played tracks the member requested, newest first
+    let lowercase_query = query.to_lowercase();
+    let recent: Vec<TrackData> = get_sessions(&lava_client)?
+        .read()
+        .await
+        .get(&guild_id)
+        .map(|session| {
+            session
+                .history
+                .iter()
+                .rev()
+                .filter(|track| {
+                    get_requester(track) == Some(interaction.user.id)
+                        && track.info.title.to_lowercase().contains(&lowercase_query)
+                })
+                .take(MAX_RECENT_SUGGESTIONS)
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    let results = if query.chars().count() < MIN_SUGGESTION_QUERY {
+        Vec::new()
     } else {
-        if let Some(url) = url {
-            format!("Started playing [{name}]({url})")
-        } else {
+        load_suggestions(&lava_client, guild_id, interaction, source, query)
+            .await?
+            .unwrap_or_default()
+    };
+
+    // Choices play the track by its url, so tracks with long or no urls can't be suggested
+    let mut suggested = Vec::new();
+    let choices = recent
+        .iter()
+        .map(|track| (format!("🕘 {}", track.info.title), track))
+        .chain(results.iter().map(|track| {
+            (
+                format!("{} - {}", track.info.title, track.info.author),
+                track,
+            )
+        }));
+    for (name, track) in choices {
+        if suggested.len() >= MAX_SUGGESTIONS {
+            break;
+        }
+        if let Some(uri) = &track.info.uri
+            && uri.len() <= MAX_OPTION_LENGTH
+            && !suggested.contains(uri)
+        {
+            response = response.add_string_choice(truncate(&name, MAX_OPTION_LENGTH), uri);
+            suggested.push(uri.clone());
+        }
+    }
+    Ok(response)
+}
+
+/// Load search results for a query, or None if the member kept typing and a newer query replaced it.
+/// Results are cached for a while as the same queries come up again when members type.
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `interaction` - autocomplete interaction
+/// * `source` - where to search
+/// * `query` - what the member has typed, without a source prefix
+///
+async fn load_suggestions(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    interaction: &CommandInteraction,
+    source: SearchSource,
+    query: &str,
+) -> Result<Option<Vec<TrackData>>, Box<dyn Error + Sync + Send>> {
+    let data = lava_client.data::<LavalinkData>()?;
+    let key = format!("{}:{query}", source.prefix());
+    {
+        // Newer request replaces the one the member may still have waiting
+        let mut suggestions = data.suggestions.lock().await;
+        if let Some((loaded, results)) = suggestions.results.get(&key)
+            && loaded.elapsed() < SUGGESTION_LIFETIME
+        {
+            let results = results.clone();
+            suggestions.latest.remove(&interaction.user.id);
+            return Ok(Some(results));
+        }
+        suggestions
+            .latest
+            .insert(interaction.user.id, interaction.id);
+    }
+
+    // Wait for the member to stop typing, request is forgotten once it is no longer waiting
+    tokio::time::sleep(AUTOCOMPLETE_DEBOUNCE).await;
+    {
+        let mut suggestions = data.suggestions.lock().await;
+        if suggestions.latest.get(&interaction.user.id) != Some(&interaction.id) {
+            return Ok(None);
+        }
+        suggestions.latest.remove(&interaction.user.id);
+    }
+
+    let results = match lava_client
+        .load_tracks(guild_id.get(), &source.engine().to_query(query)?)
+        .await?
+        .data
+    {
+        Some(TrackLoadData::Search(results)) => results,
+        _ => Vec::new(),
+    };
+
+    // Forget old results and make room for new ones
+    let mut suggestions = data.suggestions.lock().await;
+    suggestions
+        .results
+        .retain(|_, (loaded, _)| loaded.elapsed() < SUGGESTION_LIFETIME);
+    if suggestions.results.len() >= MAX_CACHED_QUERIES
+        && let Some(oldest) = suggestions
+            .results
+            .iter()
+            .min_by_key(|(_, (loaded, _))| *loaded)
+            .map(|(query, _)| query.clone())
+    {
+        suggestions.results.remove(&oldest);
+    }
+    suggestions
+        .results
+        .insert(key, (Instant::now(), results.clone()));
+    Ok(Some(results))
+}
+
+/// Get the source chosen with the engine option of a command
+pub fn chosen_source(options: &[ResolvedOption]) -> Result<Option<SearchSource>, FerrisError> {
+    match get_option(options, "engine") {
+        Some(ResolvedValue::String(engine)) => Ok(Some(
+            SearchSource::from_name(engine).ok_or(FerrisError::MissingArguments)?,
+        )),
+        _ => Ok(None),
+    }
+}
+
+/// Join the voice channel of the member unless Ferris is already on a voice channel
+pub async fn join_if_needed(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+) -> Result<(), Box<dyn Error + Sync + Send>> {
+    // Return error if Join gives some other error then already being on call
+    if let Err(error) = join(ctx, interaction).await {
+        let ferris_error = *error.downcast::<FerrisError>()?;
+        if !matches!(ferris_error, FerrisError::AlreadyOnCallError) {
+            return Err(ferris_error)?;
+        }
+    }
+    Ok(())
+}
+
+/// Queue loaded tracks requested by a member within limits of the guild and describe where they went
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `player` - player context of the guild
+/// * `user_id` - member requesting the tracks
+/// * `tracks` - loaded tracks
+/// * `name` - name of the track or playlist shown in the description
+/// * `position` - where in queue the tracks go
+///
+pub async fn queue_loaded_tracks(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    player: &PlayerContext,
+    user_id: UserId,
+    tracks: Vec<TrackData>,
+    name: &str,
+    position: QueuePosition,
+) -> Result<String, Box<dyn Error + Sync + Send>> {
+    let found = tracks.len();
+    let (tracks, limit) = apply_limits(
+        tracks,
+        &get_settings(lava_client, guild_id).await?,
+        &get_queue(lava_client, guild_id).await?.get_queue().await?,
+        user_id,
+    )?;
+    let left_out = found - tracks.len();
+    let tracks: Vec<TrackInQueue> = tracks
+        .into_iter()
+        .map(|mut track| {
+            set_requester(&mut track, user_id);
+            track.into()
+        })
+        .collect();
+
+    let (shuffle, loop_mode, fair_queue) = get_sessions(lava_client)?
+        .read()
+        .await
+        .get(&guild_id)
+        .map(|session| {
+            (
+                session.shuffle,
+                session.loop_mode,
+                session.settings.fair_queue,
+            )
+        })
+        .unwrap_or_default();
+    let current_track = player.get_player().await?.track;
+    // Tracks added to the end take turns with tracks of other requesters
+    let fair = fair_queue && position == QueuePosition::End;
+    let description = match (current_track, position) {
+        (Some(current_track), QueuePosition::Now) => {
+            // Interrupted track shouldn't come back when looping it, but it stays in a looping queue
+            if loop_mode == LoopMode::Track {
+                remove_loop_copy(player, loop_mode, &current_track).await?;
+            }
+            player.play_now(&tracks[0].track).await?;
+            queue_tracks(player, tracks[1..].to_vec(), position, shuffle, loop_mode).await?;
+            format!("Started playing {name}")
+        }
+        (Some(current_track), _) if fair => {
+            queue_fairly(player, tracks, get_requester(&current_track), loop_mode).await?;
+            format!("Added {name} to queue, requesters take turns")
+        }
+        (Some(_), _) => match queue_tracks(player, tracks, position, shuffle, loop_mode).await? {
+            Some(index) => format!("Added {name} to queue at position {index}"),
+            None => format!("Added {name} to random positions in queue"),
+        },
+        (None, _) => {
+            player.play(&tracks[0].track).await?;
+            if fair {
+                queue_fairly(player, tracks[1..].to_vec(), Some(user_id), loop_mode).await?;
+            } else {
+                queue_tracks(player, tracks[1..].to_vec(), position, shuffle, loop_mode).await?;
+            }
             format!("Started playing {name}")
         }
     };
 
-    player.play(&tracks[0].track).await?;
+    Ok(match limit {
+        Some(limit) => format!("{description}\n{left_out} tracks were left out: {limit}"),
+        None => description,
+    })
+}
 
-    // Respond in Discord
-    Ok(Response::new().description(&description).build())
+/// Where in queue new tracks go
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueuePosition {
+    /// End of the queue, or random positions when shuffling
+    End,
+    /// Front of the queue
+    Next,
+    /// Replace current track, rest go to the front of the queue
+    Now,
+    /// Specific position, numbered like in queue command
+    Index(usize),
+}
+
+impl QueuePosition {
+    fn parse(position: &str) -> Option<Self> {
+        match position.trim() {
+            "end" => Some(QueuePosition::End),
+            "next" => Some(QueuePosition::Next),
+            "now" => Some(QueuePosition::Now),
+            index => index.parse().ok().map(QueuePosition::Index),
+        }
+    }
+}
+
+/// Add tracks to queue and return the position of the first one, or None if they were shuffled
+///
+/// # Arguments
+///
+/// * `player` - player context of the guild
+/// * `tracks` - tracks to add
+/// * `position` - where in queue the tracks go
+/// * `shuffle` - whether tracks added to the end of queue go to random positions instead
+/// * `loop_mode` - looping mode of the guild, copy of a looping track stays at the front
+///
+async fn queue_tracks(
+    player: &PlayerContext,
+    tracks: Vec<TrackInQueue>,
+    position: QueuePosition,
+    shuffle: bool,
+    loop_mode: LoopMode,
+) -> Result<Option<usize>, FerrisError> {
+    let queue = player.get_queue();
+    let mut count = queue
+        .get_count()
+        .await
+        .map_err(|_| FerrisError::LavalinkError)?;
+    let first = usize::from(loop_mode == LoopMode::Track).min(count);
+
+    let index = match position {
+        QueuePosition::End if shuffle => {
+            let mut rng = rand::rng();
+            for track in tracks {
+                queue
+                    .insert(rng.random_range(first..=count), track)
+                    .map_err(|_| FerrisError::LavalinkError)?;
+                count += 1;
+            }
+            return Ok(None);
+        }
+        QueuePosition::End => count,
+        QueuePosition::Next | QueuePosition::Now => first,
+        QueuePosition::Index(index) => index.clamp(first, count),
+    };
+    for (offset, track) in tracks.into_iter().enumerate() {
+        queue
+            .insert(index + offset, track)
+            .map_err(|_| FerrisError::LavalinkError)?;
+    }
+    Ok(Some(index))
+}
+
+/// Insert tracks where requesters keep taking turns in queue. Copy of a looping track stays at the front.
+///
+/// # Arguments
+///
+/// * `player` - player context of the guild
+/// * `tracks` - tracks to add
+/// * `current_requester` - requester of the current track, who has the last turn
+/// * `loop_mode` - looping mode of the guild
+///
+async fn queue_fairly(
+    player: &PlayerContext,
+    tracks: Vec<TrackInQueue>,
+    current_requester: Option<UserId>,
+    loop_mode: LoopMode,
+) -> Result<(), FerrisError> {
+    let queue = player.get_queue();
+    let queued = queue
+        .get_queue()
+        .await
+        .map_err(|_| FerrisError::LavalinkError)?;
+    let first = usize::from(loop_mode == LoopMode::Track).min(queued.len());
+    let mut requesters: Vec<Option<UserId>> = queued
+        .iter()
+        .skip(first)
+        .map(|queued| get_requester(&queued.track))
+        .collect();
+
+    // Insert tracks one by one instead of replacing the queue, which could bring back a track that just started
+    for track in tracks {
+        let requester = get_requester(&track.track);
+        let index = round_robin_position(&requesters, requester, Some(current_requester));
+        queue
+            .insert(first + index, track)
+            .map_err(|_| FerrisError::LavalinkError)?;
+        requesters.insert(index, requester);
+    }
+    Ok(())
+}
+
+/// Drop tracks that break limits of the guild. Returns the tracks left and the first limit that dropped tracks,
+/// or the limit as an error if no tracks were left.
+///
+/// # Arguments
+///
+/// * `tracks` - tracks to queue
+/// * `settings` - settings of the guild
+/// * `queue` - tracks already in queue
+/// * `user_id` - member queueing the tracks
+///
+fn apply_limits(
+    tracks: Vec<TrackData>,
+    settings: &GuildSettings,
+    queue: &VecDeque<TrackInQueue>,
+    user_id: UserId,
+) -> Result<(Vec<TrackData>, Option<FerrisError>), FerrisError> {
+    let mut limit = None;
+    let mut tracks: Vec<TrackData> = tracks
+        .into_iter()
+        .filter(|track| {
+            if track.info.is_stream {
+                if !settings.allow_streams {
+                    limit.get_or_insert(FerrisError::StreamLimitError);
+                    return false;
+                }
+            } else if let Some(max) = settings.max_track_minutes
+                && Duration::from_millis(track.info.length) > Duration::from_secs(max * 60)
+            {
+                limit.get_or_insert(FerrisError::TrackDurationLimitError(max));
+                return false;
+            }
+            true
+        })
+        .collect();
+
+    // Only queue as many tracks as there is room for
+    if let Some(max) = settings.max_queue_length {
+        let room = max.saturating_sub(queue.len());
+        if tracks.len() > room {
+            tracks.truncate(room);
+            limit.get_or_insert(FerrisError::QueueLengthLimitError(max));
+        }
+    }
+    if let Some(max) = settings.max_user_tracks {
+        let queued = queue
+            .iter()
+            .filter(|track| get_requester(&track.track) == Some(user_id))
+            .count();
+        let room = max.saturating_sub(queued);
+        if tracks.len() > room {
+            tracks.truncate(room);
+            limit.get_or_insert(FerrisError::UserTrackLimitError(max));
+        }
+    }
+
+    match limit {
+        _ if !tracks.is_empty() => Ok((tracks, limit)),
+        Some(limit) => Err(limit),
+        None => Err(FerrisError::TrackNotFoundError),
+    }
 }
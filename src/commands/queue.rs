@@ -1,9 +1,15 @@
 use super::get_command_context;
-use crate::{FerrisError, FerrisResponse, LoopingTrack, MAX_DESCRIPTION_SIZE, Response, get_queue};
-use lavalink_rs::player_context::TrackInQueue;
-use serenity::{all::CommandInteraction, client::Context, futures::StreamExt};
-use std::fmt::Write;
-use tokio::sync::RwLock;
+use crate::{
+    FerrisError, FerrisResponse, MAX_DESCRIPTION_SIZE, Response, get_queue, get_requester,
+    requested_by, round_robin,
+    session::{LoopMode, get_sessions},
+};
+use lavalink_rs::{
+    client::LavalinkClient,
+    player_context::{PlayerContext, TrackInQueue},
+};
+use serenity::{all::CommandInteraction, client::Context, futures::StreamExt, model::id::GuildId};
+use std::{collections::VecDeque, fmt::Write};
 
 pub async fn queue(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
     // Init variables
@@ -17,19 +23,39 @@ pub async fn queue(ctx: &Context, interaction: &CommandInteraction) -> FerrisRes
         Err(FerrisError::QueueEmptyError)?;
     }
 
-    let mutex = lava_client.data::<RwLock<Option<LoopingTrack>>>()?;
-    let looping = mutex.read().await;
+    let (loop_mode, shuffle, fair_queue) = get_sessions(&lava_client)?
+        .read()
+        .await
+        .get(&guild_id)
+        .map(|session| {
+            (
+                session.loop_mode,
+                session.shuffle,
+                session.settings.fair_queue,
+            )
+        })
+        .unwrap_or_default();
 
     // Construct queue
     let mut queue_string = String::new();
+    if loop_mode != LoopMode::Off {
+        writeln!(queue_string, "{loop_mode}")?;
+    }
+    if shuffle {
+        writeln!(queue_string, "🔀 Shuffling new tracks")?;
+    }
+    if fair_queue {
+        writeln!(queue_string, "⚖️ Requesters take turns")?;
+    }
     for (index, track) in queue.into_iter().enumerate() {
+        let requester = requested_by(&track.track);
         let (title, uri) = (
             track.track.info.title,
             track.track.info.uri.unwrap_or("Unknown".to_string()),
         );
-        let mut track_string = format!("{index}. [{title}]({uri})");
-        if looping.is_some() && index == 0 {
-            track_string.push_str(" 🔁");
+        let mut track_string = format!("{index}. [{title}]({uri}){requester}");
+        if loop_mode == LoopMode::Track && index == 0 {
+            track_string.push_str(" 🔂");
         }
 
         // Prevent writing to more tracks to queue string if we are nearing the message limit
@@ -46,3 +72,132 @@ pub async fn queue(ctx: &Context, interaction: &CommandInteraction) -> FerrisRes
         .description(&queue_string)
         .build())
 }
+
+/// Reorder tracks in queue. Copy of a track looping in track mode stays at the front of the queue.
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `reorder` - function which reorders the tracks
+///
+pub async fn reorder_queue(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    reorder: impl FnOnce(&mut [TrackInQueue]),
+) -> Result<usize, FerrisError> {
+    let loop_mode = get_sessions(lava_client)?
+        .read()
+        .await
+        .get(&guild_id)
+        .map(|session| session.loop_mode)
+        .unwrap_or_default();
+    edit_queue(lava_client, guild_id, |tracks| {
+        if tracks.is_empty() {
+            Err(FerrisError::QueueEmptyError)?
+        }
+        let fixed = usize::from(loop_mode == LoopMode::Track).min(tracks.len());
+        reorder(&mut tracks.make_contiguous()[fixed..]);
+        Ok(tracks.len() - fixed)
+    })
+    .await
+}
+
+/// Edit the queue and write it back whole. A track that started between reading and writing the queue
+/// would be put back by the write, so it is taken out of the queue again.
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `edit` - function which edits the tracks, queue is left as it is if it fails
+///
+pub async fn edit_queue<T>(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    edit: impl FnOnce(&mut VecDeque<TrackInQueue>) -> Result<T, FerrisError>,
+) -> Result<T, FerrisError> {
+    let queue = get_queue(lava_client, guild_id).await?;
+    let playing = playing_track(lava_client, guild_id).await?;
+    let mut tracks = queue
+        .get_queue()
+        .await
+        .map_err(|_| FerrisError::LavalinkError)?;
+    let next = tracks.front().map(|track| track.track.encoded.clone());
+    let result = edit(&mut tracks)?;
+    queue
+        .replace(tracks)
+        .map_err(|_| FerrisError::LavalinkError)?;
+
+    // Queue messages are handled in order, so the new queue is in place once the count arrives
+    queue
+        .get_count()
+        .await
+        .map_err(|_| FerrisError::LavalinkError)?;
+    let started = playing_track(lava_client, guild_id).await?;
+    if started.is_some() && started != playing && started == next {
+        let tracks = queue
+            .get_queue()
+            .await
+            .map_err(|_| FerrisError::LavalinkError)?;
+        if let Some(index) = tracks
+            .iter()
+            .position(|track| Some(&track.track.encoded) == started.as_ref())
+        {
+            queue
+                .remove(index)
+                .map_err(|_| FerrisError::LavalinkError)?;
+        }
+    }
+    Ok(result)
+}
+
+/// Encoded track playing on Lavalink. Lavalink is asked directly as the player context only learns about
+/// a new track when its start event arrives.
+async fn playing_track(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+) -> Result<Option<String>, FerrisError> {
+    Ok(lava_client
+        .request_player(guild_id.get())
+        .await
+        .map_err(|_| FerrisError::LavalinkError)?
+        .track
+        .map(|track| track.encoded))
+}
+
+/// Reorder queue so that requesters take turns. Requester of the current track has the last turn.
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `player` - player context of the guild
+///
+pub async fn apply_fair_queue(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    player: &PlayerContext,
+) -> Result<(), FerrisError> {
+    let current_requester = player
+        .get_player()
+        .await
+        .map_err(|_| FerrisError::LavalinkError)?
+        .track
+        .and_then(|track| get_requester(&track));
+    let result = reorder_queue(lava_client, guild_id, |tracks| {
+        let ordered = round_robin(
+            tracks.to_vec(),
+            |track| get_requester(&track.track),
+            current_requester.map(Some),
+        );
+        tracks.clone_from_slice(&ordered);
+    })
+    .await;
+
+    // Nothing to reorder in an empty queue
+    match result {
+        Err(FerrisError::QueueEmptyError) | Ok(_) => Ok(()),
+        Err(error) => Err(error),
+    }
+}
@@ -0,0 +1,46 @@
+use super::{get_args, get_command_context};
+use crate::{
+    FerrisError, FerrisResponse, Response, get_queue, get_requester, parse_range,
+    permissions::{CommandPermission, has_dj_access},
+    session::get_settings,
+};
+use serenity::{all::CommandInteraction, client::Context};
+use std::fmt::Write;
+
+pub async fn remove(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+    let range =
+        parse_range(&get_args(&interaction.data.options)?).ok_or(FerrisError::QueueIndexError)?;
+    let queue = get_queue(&lava_client, guild_id).await?;
+    let tracks = queue.get_queue().await?;
+
+    if *range.end() >= tracks.len() {
+        Err(FerrisError::QueueIndexError)?
+    }
+
+    // Members without DJ access can only remove their own tracks
+    if !has_dj_access(&get_settings(&lava_client, guild_id).await?, interaction)
+        && tracks
+            .range(range.clone())
+            .any(|track| get_requester(&track.track) != Some(interaction.user.id))
+    {
+        Err(FerrisError::PermissionError(CommandPermission::Requester))?
+    }
+
+    // Remove from the start of the range as later tracks move forward
+    let mut description = String::new();
+    for index in range.clone() {
+        queue.remove(*range.start())?;
+        let info = &tracks[index].track.info;
+        writeln!(
+            description,
+            "Removed {index}. [{}]({})",
+            info.title,
+            info.uri.as_deref().unwrap_or("Unknown")
+        )?;
+    }
+
+    // Respond in Discord
+    Ok(Response::new().description(&description).build())
+}
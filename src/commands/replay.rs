@@ -0,0 +1,24 @@
+use super::get_command_context;
+use crate::{FerrisError, FerrisResponse, Response};
+use serenity::{all::CommandInteraction, client::Context};
+use std::time::Duration;
+
+pub async fn replay(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (_, _, Some(player), _) = get_command_context(ctx, interaction).await? else {
+        Err(FerrisError::LavalinkError)?
+    };
+    let track = player
+        .get_player()
+        .await?
+        .track
+        .ok_or(FerrisError::QueueEmptyError)?;
+
+    // Send command to Lavalink
+    player.set_position(Duration::ZERO).await?;
+
+    // Respond in Discord
+    Ok(Response::new()
+        .description(&format!("Restarted {}", track.info.title))
+        .build())
+}
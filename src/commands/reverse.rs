@@ -0,0 +1,16 @@
+use super::{get_command_context, queue::reorder_queue};
+use crate::{FerrisResponse, Response};
+use serenity::{all::CommandInteraction, client::Context};
+
+pub async fn reverse(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+
+    // Send command to Lavalink
+    let count = reorder_queue(&lava_client, guild_id, |tracks| tracks.reverse()).await?;
+
+    // Respond in Discord
+    Ok(Response::new()
+        .description(&format!("Reversed {count} tracks"))
+        .build())
+}
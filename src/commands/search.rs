@@ -0,0 +1,197 @@
+use super::{
+    get_command_context, get_option,
+    play::{QueuePosition, chosen_source, join_if_needed, queue_loaded_tracks},
+};
+use crate::{
+    FerrisError, Lavalink, MAX_OPTION_LENGTH, Response, SEARCH_LIFETIME, SEARCH_RESULTS,
+    format_duration,
+    session::{PendingSearch, get_sessions, get_settings},
+    sources::pick_source,
+    truncate,
+};
+use lavalink_rs::{model::track::TrackData, prelude::TrackLoadData};
+use serenity::{
+    all::{CommandInteraction, ComponentInteraction, ComponentInteractionDataKind, ResolvedValue},
+    builder::{
+        CreateActionRow, CreateEmbed, CreateSelectMenu, CreateSelectMenuKind,
+        CreateSelectMenuOption,
+    },
+    client::Context,
+    model::id::InteractionId,
+};
+use std::{
+    error::Error,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+/// Search for tracks and respond with a menu for picking one of the results
+pub async fn search(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+) -> Result<(CreateEmbed, Vec<CreateActionRow>), Box<dyn Error + Sync + Send>> {
+    // Picked track is queued later so join now
+    join_if_needed(ctx, interaction).await?;
+
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+    let options = interaction.data.options();
+    let Some(ResolvedValue::String(query)) = get_option(&options, "query") else {
+        Err(FerrisError::MissingArguments)?
+    };
+    let default_source = get_settings(&lava_client, guild_id).await?.default_source;
+    let (source, query) = pick_source(query, chosen_source(&options)?, default_source);
+    let query_result = lava_client
+        .load_tracks(guild_id.get(), &source.engine().to_query(query)?)
+        .await?;
+    let Some(TrackLoadData::Search(mut results)) = query_result.data else {
+        Err(FerrisError::TrackNotFoundError)?
+    };
+    results.truncate(SEARCH_RESULTS);
+    if results.is_empty() {
+        Err(FerrisError::TrackNotFoundError)?
+    }
+
+    // Construct results and menu
+    let mut description = String::new();
+    let mut menu_options = Vec::new();
+    for (index, track) in results.iter().enumerate() {
+        let length = track_length(track);
+        writeln!(
+            description,
+            "{}. [{}]({}) by {} ({length})",
+            index + 1,
+            track.info.title,
+            track.info.uri.as_deref().unwrap_or("Unknown"),
+            track.info.author
+        )?;
+        menu_options.push(
+            CreateSelectMenuOption::new(
+                truncate(
+                    &format!("{}. {}", index + 1, track.info.title),
+                    MAX_OPTION_LENGTH,
+                ),
+                index.to_string(),
+            )
+            .description(truncate(
+                &format!("{} ({length})", track.info.author),
+                MAX_OPTION_LENGTH,
+            )),
+        );
+    }
+    let menu = CreateSelectMenu::new(
+        format!("search:{}", interaction.id),
+        CreateSelectMenuKind::String {
+            options: menu_options,
+        },
+    )
+    .placeholder("Pick a track to queue");
+
+    // Keep results until the member picks one, forgetting searches nobody picked from
+    {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        let searches = &mut sessions.entry(guild_id).or_default().searches;
+        searches.retain(|_, search| search.searched_at.elapsed() < SEARCH_LIFETIME);
+        searches.insert(
+            interaction.id,
+            PendingSearch {
+                user_id: interaction.user.id,
+                searched_at: Instant::now(),
+                results,
+            },
+        );
+    }
+
+    // Respond in Discord
+    Ok((
+        Response::new()
+            .title("Search results")
+            .description(&description)
+            .build(),
+        vec![CreateActionRow::SelectMenu(menu)],
+    ))
+}
+
+/// Queue the search result a member picked from the menu
+pub async fn pick(
+    ctx: &Context,
+    interaction: &ComponentInteraction,
+) -> Result<CreateEmbed, Box<dyn Error + Sync + Send>> {
+    // Init variables
+    let guild_id = interaction.guild_id.ok_or(FerrisError::GuildError)?;
+    let lava_client = ctx
+        .data
+        .read()
+        .await
+        .get::<Lavalink>()
+        .ok_or(FerrisError::LavalinkError)?
+        .clone();
+    let player = lava_client
+        .get_player_context(guild_id.get())
+        .ok_or(FerrisError::NotOnCallError)?;
+
+    // Only the member who searched can pick
+    let search_id: InteractionId = interaction
+        .data
+        .custom_id
+        .split_once(':')
+        .and_then(|(_, search_id)| search_id.parse().ok())
+        .ok_or(FerrisError::SearchExpiredError)?;
+    let ComponentInteractionDataKind::StringSelect { values } = &interaction.data.kind else {
+        Err(FerrisError::MissingArguments)?
+    };
+    let index: usize = values
+        .first()
+        .and_then(|value| value.parse().ok())
+        .ok_or(FerrisError::MissingArguments)?;
+    let track = {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        let searches = &mut sessions
+            .get_mut(&guild_id)
+            .ok_or(FerrisError::SearchExpiredError)?
+            .searches;
+        let search = searches
+            .get(&search_id)
+            .filter(|search| search.searched_at.elapsed() < SEARCH_LIFETIME)
+            .ok_or(FerrisError::SearchExpiredError)?;
+        if search.user_id != interaction.user.id {
+            Err(FerrisError::SearchOwnerError)?
+        }
+        searches
+            .remove(&search_id)
+            .and_then(|mut search| {
+                (index < search.results.len()).then(|| search.results.swap_remove(index))
+            })
+            .ok_or(FerrisError::SearchExpiredError)?
+    };
+    let name = match &track.info.uri {
+        Some(uri) => format!("[{}]({uri})", track.info.title),
+        None => track.info.title.clone(),
+    };
+
+    // Send command to Lavalink
+    let description = queue_loaded_tracks(
+        &lava_client,
+        guild_id,
+        &player,
+        interaction.user.id,
+        vec![track],
+        &name,
+        QueuePosition::End,
+    )
+    .await?;
+
+    // Respond in Discord
+    Ok(Response::new().description(&description).build())
+}
+
+/// Length of a track, or LIVE for streams
+pub fn track_length(track: &TrackData) -> String {
+    if track.info.is_stream {
+        "LIVE".to_string()
+    } else {
+        format_duration(Duration::from_millis(track.info.length))
+    }
+}
@@ -0,0 +1,293 @@
+use super::{
+    get_command_context, get_option, get_subcommand, play::chosen_source, queue::apply_fair_queue,
+};
+use crate::{
+    FerrisError, FerrisResponse, MAX_IDLE_MINUTES, MAX_VOLUME, Response, session::get_sessions,
+    sources::parse_sources,
+};
+use lavalink_rs::{client::LavalinkClient, prelude::PlayerContext};
+use serenity::{
+    all::{CommandInteraction, Mentionable, ResolvedOption, ResolvedValue},
+    client::Context,
+    model::id::GuildId,
+};
+use std::time::Duration;
+
+/// Admin command for changing settings of the guild
+pub async fn settings(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, player, _) = get_command_context(ctx, interaction).await?;
+    let options = interaction.data.options();
+    let (subcommand, sub_options) = get_subcommand(&options)?;
+
+    let description = match subcommand {
+        "volume" => volume_settings(&lava_client, guild_id, player.as_ref(), sub_options).await?,
+        "idle" => idle_settings(&lava_client, guild_id, sub_options).await?,
+        "voteskip" => vote_skip_settings(&lava_client, guild_id, sub_options).await?,
+        "dj" => dj_settings(&lava_client, guild_id, sub_options).await?,
+        "limits" => limit_settings(&lava_client, guild_id, sub_options).await?,
+        "fairqueue" => fair_queue_settings(&lava_client, guild_id, player, sub_options).await?,
+        "source" => source_settings(&lava_client, guild_id, sub_options).await?,
+        "fallback" => fallback_settings(&lava_client, guild_id, sub_options).await?,
+        _ => Err(FerrisError::MissingArguments)?,
+    };
+
+    // Respond in Discord
+    Ok(Response::new()
+        .title("Settings")
+        .description(&description)
+        .build())
+}
+
+async fn volume_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    player: Option<&PlayerContext>,
+    options: &[ResolvedOption<'_>],
+) -> Result<String, FerrisError> {
+    let (default_volume, max_volume) = {
+        let sessions = get_sessions(lava_client)?;
+        let mut sessions = sessions.write().await;
+        let settings = &mut sessions.entry(guild_id).or_default().settings;
+
+        if let Some(ResolvedValue::Integer(max)) = get_option(options, "max") {
+            settings.max_volume = u16::try_from(*max)
+                .ok()
+                .filter(|max| *max <= MAX_VOLUME)
+                .ok_or(FerrisError::VolumeLimitError(MAX_VOLUME))?;
+        }
+        if let Some(ResolvedValue::Integer(default)) = get_option(options, "default") {
+            settings.default_volume = u16::try_from(*default)
+                .ok()
+                .filter(|default| *default <= settings.max_volume)
+                .ok_or(FerrisError::VolumeLimitError(settings.max_volume))?;
+        }
+        // Keep default volume within the limit
+        settings.default_volume = settings.default_volume.min(settings.max_volume);
+        (settings.default_volume, settings.max_volume)
+    };
+
+    // Keep current volume within the limit too
+    if let Some(player) = player
+        && player
+            .get_player()
+            .await
+            .map_err(|_| FerrisError::LavalinkError)?
+            .volume
+            > max_volume
+    {
+        player
+            .set_volume(max_volume)
+            .await
+            .map_err(|_| FerrisError::LavalinkError)?;
+    }
+
+    Ok(format!(
+        "Default volume: {default_volume}%\nMaximum volume: {max_volume}%"
+    ))
+}
+
+async fn idle_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    options: &[ResolvedOption<'_>],
+) -> Result<String, FerrisError> {
+    let Some(ResolvedValue::Integer(minutes)) = get_option(options, "minutes") else {
+        Err(FerrisError::MissingArguments)?
+    };
+    let minutes = u64::try_from(*minutes)
+        .ok()
+        .filter(|minutes| (1..=MAX_IDLE_MINUTES).contains(minutes))
+        .ok_or(FerrisError::MissingArguments)?;
+
+    get_sessions(lava_client)?
+        .write()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .settings
+        .idle_timeout = Duration::from_secs(minutes * 60);
+
+    Ok(format!(
+        "Leaving voice channel after {minutes} minutes alone or idle"
+    ))
+}
+
+async fn vote_skip_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    options: &[ResolvedOption<'_>],
+) -> Result<String, FerrisError> {
+    let sessions = get_sessions(lava_client)?;
+    let mut sessions = sessions.write().await;
+    let settings = &mut sessions.entry(guild_id).or_default().settings;
+
+    if let Some(ResolvedValue::Boolean(enabled)) = get_option(options, "enabled") {
+        settings.vote_skip = *enabled;
+    }
+    if let Some(ResolvedValue::Number(ratio)) = get_option(options, "ratio") {
+        settings.vote_skip_ratio = *ratio;
+    }
+
+    Ok(if settings.vote_skip {
+        format!(
+            "Skipping needs votes from {:.0}% of listeners",
+            settings.vote_skip_ratio * 100.0
+        )
+    } else {
+        "Anyone can skip tracks".to_string()
+    })
+}
+
+async fn dj_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    options: &[ResolvedOption<'_>],
+) -> Result<String, FerrisError> {
+    let dj_role = match get_option(options, "role") {
+        Some(ResolvedValue::Role(role)) => Some(role.id),
+        _ => None,
+    };
+
+    get_sessions(lava_client)?
+        .write()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .settings
+        .dj_role = dj_role;
+
+    Ok(match dj_role {
+        Some(role_id) => format!("Only {} can control the player", role_id.mention()),
+        None => "Anyone can control the player".to_string(),
+    })
+}
+
+async fn fair_queue_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    player: Option<PlayerContext>,
+    options: &[ResolvedOption<'_>],
+) -> Result<String, FerrisError> {
+    let Some(ResolvedValue::Boolean(enabled)) = get_option(options, "enabled") else {
+        Err(FerrisError::MissingArguments)?
+    };
+
+    get_sessions(lava_client)?
+        .write()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .settings
+        .fair_queue = *enabled;
+
+    // Tracks already in queue start taking turns right away
+    if *enabled && let Some(player) = player {
+        apply_fair_queue(lava_client, guild_id, &player).await?;
+    }
+
+    Ok(if *enabled {
+        "Requesters take turns in queue".to_string()
+    } else {
+        "Tracks are queued in the order they are added".to_string()
+    })
+}
+
+async fn source_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    options: &[ResolvedOption<'_>],
+) -> Result<String, FerrisError> {
+    let source = chosen_source(options)?.ok_or(FerrisError::MissingArguments)?;
+
+    get_sessions(lava_client)?
+        .write()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .settings
+        .default_source = source;
+
+    Ok(format!("Searching from {source} by default"))
+}
+
+async fn fallback_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    options: &[ResolvedOption<'_>],
+) -> Result<String, FerrisError> {
+    let Some(ResolvedValue::String(sources)) = get_option(options, "sources") else {
+        Err(FerrisError::MissingArguments)?
+    };
+    let sources =
+        parse_sources(sources).map_err(|name| FerrisError::UnknownSourceError(name.to_string()))?;
+    let description = if sources.is_empty() {
+        "Only the chosen source is searched".to_string()
+    } else {
+        format!(
+            "Searching from {} when the chosen source finds nothing",
+            sources
+                .iter()
+                .map(|source| source.to_string())
+                .collect::<Vec<_>>()
+                .join(", then ")
+        )
+    };
+
+    get_sessions(lava_client)?
+        .write()
+        .await
+        .entry(guild_id)
+        .or_default()
+        .settings
+        .fallback_sources = sources;
+
+    Ok(description)
+}
+
+async fn limit_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    options: &[ResolvedOption<'_>],
+) -> Result<String, FerrisError> {
+    let sessions = get_sessions(lava_client)?;
+    let mut sessions = sessions.write().await;
+    let settings = &mut sessions.entry(guild_id).or_default().settings;
+
+    // Zero removes a limit
+    let limit = |name| match get_option(options, name) {
+        Some(ResolvedValue::Integer(limit)) => {
+            Some(u64::try_from(*limit).ok().filter(|limit| *limit > 0))
+        }
+        _ => None,
+    };
+    if let Some(max) = limit("user_tracks") {
+        settings.max_user_tracks = max.map(|max| max as usize);
+    }
+    if let Some(max) = limit("queue_length") {
+        settings.max_queue_length = max.map(|max| max as usize);
+    }
+    if let Some(max) = limit("track_minutes") {
+        settings.max_track_minutes = max;
+    }
+    if let Some(ResolvedValue::Boolean(allow)) = get_option(options, "streams") {
+        settings.allow_streams = *allow;
+    }
+
+    let describe = |max: Option<String>| max.unwrap_or("No limit".to_string());
+    Ok(format!(
+        "Tracks per member: {}\nQueue length: {}\nTrack length: {}\nLive streams: {}",
+        describe(settings.max_user_tracks.map(|max| max.to_string())),
+        describe(settings.max_queue_length.map(|max| max.to_string())),
+        describe(
+            settings
+                .max_track_minutes
+                .map(|max| format!("{max} minutes"))
+        ),
+        if settings.allow_streams {
+            "Allowed"
+        } else {
+            "Not allowed"
+        }
+    ))
+}
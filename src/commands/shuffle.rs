@@ -0,0 +1,45 @@
+use super::{get_command_context, get_option, queue::reorder_queue};
+use crate::{FerrisResponse, Response, session::get_sessions};
+use rand::seq::SliceRandom;
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+pub async fn shuffle(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+    let options = interaction.data.options();
+
+    // Turn shuffle mode on or off if asked
+    let mode = match get_option(&options, "mode") {
+        Some(ResolvedValue::Boolean(mode)) => {
+            get_sessions(&lava_client)?
+                .write()
+                .await
+                .entry(guild_id)
+                .or_default()
+                .shuffle = *mode;
+            Some(*mode)
+        }
+        _ => None,
+    };
+
+    // Shuffle current queue unless shuffle mode was only turned off
+    let description = if mode == Some(false) {
+        "Stopped shuffling new tracks".to_string()
+    } else {
+        let count = reorder_queue(&lava_client, guild_id, |tracks| {
+            tracks.shuffle(&mut rand::rng())
+        })
+        .await?;
+        if mode == Some(true) {
+            format!("Shuffled {count} tracks and started shuffling new tracks")
+        } else {
+            format!("Shuffled {count} tracks")
+        }
+    };
+
+    // Respond in Discord
+    Ok(Response::new().description(&description).build())
+}
@@ -1,7 +1,12 @@
-use super::get_command_context;
-use crate::{FerrisError, FerrisResponse, LoopingTrack, Response, get_queue};
-use serenity::{all::CommandInteraction, client::Context};
-use tokio::sync::RwLock;
+use super::{get_command_context, loop_track::remove_loop_copy};
+use crate::{
+    FerrisError, FerrisResponse, Response, get_listeners, get_queue, get_requester,
+    permissions::{CommandPermission, has_dj_access, is_dj},
+    session::{LoopMode, get_sessions, get_settings},
+    votes_needed,
+};
+use lavalink_rs::{client::LavalinkClient, model::track::TrackData, prelude::PlayerContext};
+use serenity::{all::CommandInteraction, client::Context, model::id::GuildId};
 
 pub async fn skip(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
     // Init variables
@@ -10,33 +15,112 @@ pub async fn skip(ctx: &Context, interaction: &CommandInteraction) -> FerrisResp
         Err(FerrisError::LavalinkError)?
     };
 
-    let final_track = get_queue(&lava_client, guild_id).await?.get_count().await? == 0;
-
     let track = player
         .get_player()
         .await?
         .track
         .ok_or(FerrisError::QueueEmptyError)?;
 
-    {
-        let mutex = lava_client.data::<RwLock<Option<LoopingTrack>>>()?;
-        let mut data = mutex.write().await;
-        // If we are looping, remove looping track
-        if data.is_some() {
-            (*data) = None;
+    // Requester and DJs can always skip right away, others may need votes from listeners
+    let user_id = interaction.user.id;
+    let settings = get_settings(&lava_client, guild_id).await?;
+    let instant = get_requester(&track) == Some(user_id) || is_dj(&settings, interaction);
+    if !instant && !settings.vote_skip && !has_dj_access(&settings, interaction) {
+        Err(FerrisError::PermissionError(CommandPermission::Requester))?
+    }
+    let votes = {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        match sessions.get_mut(&guild_id) {
+            Some(session) if session.settings.vote_skip && !instant => {
+                let listeners =
+                    get_listeners(&ctx.cache, guild_id).ok_or(FerrisError::NotOnCallError)?;
+                if !listeners.contains(&user_id) {
+                    Err(FerrisError::NotListeningError)?
+                }
+
+                // Votes of members who have left don't count
+                session.skip_votes.retain(|voter| listeners.contains(voter));
+                session.skip_votes.insert(user_id);
+                let votes = session.skip_votes.len();
+                let needed = votes_needed(listeners.len(), session.settings.vote_skip_ratio);
+
+                // Only the vote that reaches the threshold skips, so simultaneous votes skip one track
+                let skip = votes >= needed && !session.vote_skipped;
+                if skip {
+                    session.vote_skipped = true;
+                }
+                Some((votes, needed, skip))
+            }
+            _ => None,
+        }
+    };
+
+    // Respond with vote count if this vote didn't skip
+    let description = match votes {
+        Some((votes, needed, false)) => {
+            return Ok(Response::new()
+                .description(&format!(
+                    "Voted to skip {} ({votes}/{needed} votes)",
+                    track.info.title
+                ))
+                .build());
+        }
+        Some((votes, needed, true)) => {
+            format!("Skipped {} ({votes}/{needed} votes)", track.info.title)
         }
-    } // Drop mutex immediately after it is no longer used
+        None => format!("Skipped {}", track.info.title),
+    };
+
+    skip_track(&lava_client, guild_id, &player, &track).await?;
+
+    // Respond in Discord
+    Ok(Response::new().description(&description).build())
+}
+
+/// Skip the current track and stop playing if it was the final one
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `player` - player context of the guild
+/// * `track` - track that is playing
+///
+async fn skip_track(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    player: &PlayerContext,
+    track: &TrackData,
+) -> Result<(), FerrisError> {
+    // When looping a track, drop its copy so that the next track starts looping instead.
+    // When looping the queue, the copy is already waiting at the end of the queue.
+    let loop_mode = get_sessions(lava_client)?
+        .read()
+        .await
+        .get(&guild_id)
+        .map(|session| session.loop_mode)
+        .unwrap_or_default();
+    if loop_mode == LoopMode::Track {
+        remove_loop_copy(player, loop_mode, track).await?;
+    }
+
+    let final_track = get_queue(lava_client, guild_id)
+        .await?
+        .get_count()
+        .await
+        .map_err(|_| FerrisError::LavalinkError)?
+        == 0;
 
     // Send command to Lavalink
-    player.skip()?;
+    player.skip().map_err(|_| FerrisError::LavalinkError)?;
 
     // If final stop playing and restart player
     if final_track {
-        player.stop_now().await?; // Stops client
+        player
+            .stop_now()
+            .await
+            .map_err(|_| FerrisError::LavalinkError)?; // Stops client
     }
-
-    // Respond in Discord
-    Ok(Response::new()
-        .description(&format!("Skipped {}", track.info.title))
-        .build())
+    Ok(())
 }
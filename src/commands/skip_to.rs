@@ -0,0 +1,49 @@
+use super::{get_command_context, get_option, move_track::to_index, queue::edit_queue};
+use crate::{FerrisError, FerrisResponse, Response};
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+pub async fn skip_to(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
+    else {
+        Err(FerrisError::LavalinkError)?
+    };
+    let options = interaction.data.options();
+    let Some(ResolvedValue::Integer(index)) = get_option(&options, "position") else {
+        Err(FerrisError::MissingArguments)?
+    };
+    let keep = matches!(
+        get_option(&options, "keep"),
+        Some(ResolvedValue::Boolean(true))
+    );
+
+    // Split queue to skipped tracks, the track to play and rest of the queue
+    let (track, description) = edit_queue(&lava_client, guild_id, |tracks| {
+        let index = to_index(*index, tracks.len())?;
+        let mut skipped = tracks.drain(..index).collect();
+        let track = tracks.pop_front().ok_or(FerrisError::QueueIndexError)?;
+        let description = if keep {
+            tracks.append(&mut skipped);
+            format!(
+                "Skipped to {} and moved {index} tracks to the end of queue",
+                track.track.info.title
+            )
+        } else {
+            format!(
+                "Skipped to {} and removed {index} tracks",
+                track.track.info.title
+            )
+        };
+        Ok((track, description))
+    })
+    .await?;
+
+    // Send command to Lavalink
+    player.play_now(&track.track).await?;
+
+    // Respond in Discord
+    Ok(Response::new().description(&description).build())
+}
@@ -0,0 +1,46 @@
+use super::{get_args, get_command_context, queue::reorder_queue};
+use crate::{FerrisError, FerrisResponse, Response, get_requester};
+use serenity::{all::CommandInteraction, client::Context};
+
+pub async fn sort(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+    let by = get_args(&interaction.data.options)?;
+
+    // Send command to Lavalink. Sorting is stable so tracks keep their order within a group.
+    let count = match by.as_str() {
+        "title" => {
+            reorder_queue(&lava_client, guild_id, |tracks| {
+                tracks.sort_by_cached_key(|track| track.track.info.title.to_lowercase())
+            })
+            .await?
+        }
+        "duration" => {
+            reorder_queue(&lava_client, guild_id, |tracks| {
+                tracks.sort_by_key(|track| track.track.info.length)
+            })
+            .await?
+        }
+        "requester" => {
+            reorder_queue(&lava_client, guild_id, |tracks| {
+                // Tracks without a requester go last
+                tracks.sort_by_key(|track| {
+                    get_requester(&track.track).map_or(u64::MAX, |id| id.get())
+                })
+            })
+            .await?
+        }
+        "source" => {
+            reorder_queue(&lava_client, guild_id, |tracks| {
+                tracks.sort_by_cached_key(|track| track.track.info.source_name.clone())
+            })
+            .await?
+        }
+        _ => Err(FerrisError::MissingArguments)?,
+    };
+
+    // Respond in Discord
+    Ok(Response::new()
+        .description(&format!("Sorted {count} tracks by {by}"))
+        .build())
+}
@@ -1,10 +1,13 @@
 use super::get_command_context;
-use crate::{FerrisError, FerrisResponse, Response};
+use crate::{
+    FerrisError, FerrisResponse, Response,
+    session::{LoopMode, get_sessions},
+};
 use serenity::{all::CommandInteraction, client::Context};
 
 pub async fn stop(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
     // Init variables
-    let (_guild_id, _lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
+    let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
     else {
         Err(FerrisError::LavalinkError)?
     };
@@ -13,5 +16,10 @@ pub async fn stop(ctx: &Context, interaction: &CommandInteraction) -> FerrisResp
     player.stop_now().await?;
     player.get_queue().clear()?;
 
+    // Nothing is left to loop
+    if let Some(session) = get_sessions(&lava_client)?.write().await.get_mut(&guild_id) {
+        session.loop_mode = LoopMode::Off;
+    }
+
     Ok(Response::new().description("Queue cleared").build())
 }
@@ -0,0 +1,41 @@
+use super::{get_command_context, get_option, move_track::to_index};
+use crate::{FerrisError, FerrisResponse, Response, get_queue};
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+pub async fn swap(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, _, _) = get_command_context(ctx, interaction).await?;
+    let options = interaction.data.options();
+    let (Some(ResolvedValue::Integer(first)), Some(ResolvedValue::Integer(second))) =
+        (get_option(&options, "a"), get_option(&options, "b"))
+    else {
+        Err(FerrisError::MissingArguments)?
+    };
+    let queue = get_queue(&lava_client, guild_id).await?;
+    let count = queue.get_count().await?;
+    let (first, second) = (to_index(*first, count)?, to_index(*second, count)?);
+
+    let first_track = queue
+        .get_track(first)
+        .await?
+        .ok_or(FerrisError::QueueIndexError)?;
+    let second_track = queue
+        .get_track(second)
+        .await?
+        .ok_or(FerrisError::QueueIndexError)?;
+
+    // Send command to Lavalink
+    queue.swap(first, second_track.clone())?;
+    queue.swap(second, first_track.clone())?;
+
+    // Respond in Discord
+    Ok(Response::new()
+        .description(&format!(
+            "Swapped {first}. {} and {second}. {}",
+            first_track.track.info.title, second_track.track.info.title
+        ))
+        .build())
+}
@@ -0,0 +1,73 @@
+use super::{get_command_context, get_option};
+use crate::{FerrisError, FerrisResponse, Response, session::get_sessions};
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+pub const MIN_TIMESCALE: f64 = 0.25;
+pub const MAX_TIMESCALE: f64 = 3.0;
+
+pub async fn speed(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    set_timescale(ctx, interaction, Timescale::Speed).await
+}
+
+pub async fn pitch(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    set_timescale(ctx, interaction, Timescale::Pitch).await
+}
+
+/// Part of Lavalink timescale filter that is changed
+enum Timescale {
+    Speed,
+    Pitch,
+}
+
+/// Set speed or pitch multiplier of the guild.
+///
+/// # Arguments
+///
+/// * `ctx` - Serenity context
+/// * `interaction` - interaction which invoked the command
+/// * `timescale` - whether to change speed or pitch
+///
+async fn set_timescale(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+    timescale: Timescale,
+) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
+    else {
+        Err(FerrisError::LavalinkError)?
+    };
+    let options = interaction.data.options();
+    let Some(ResolvedValue::Number(multiplier)) = get_option(&options, "multiplier") else {
+        Err(FerrisError::MissingArguments)?
+    };
+    let multiplier = multiplier.clamp(MIN_TIMESCALE, MAX_TIMESCALE);
+    // Normal speed or pitch doesn't need a filter
+    let value = (multiplier != 1.0).then_some(multiplier);
+
+    let (description, filters) = {
+        let sessions = get_sessions(&lava_client)?;
+        let mut sessions = sessions.write().await;
+        let session = sessions.entry(guild_id).or_default();
+        let description = match timescale {
+            Timescale::Speed => {
+                session.speed = value;
+                format!("Speed set to {multiplier}x")
+            }
+            Timescale::Pitch => {
+                session.pitch = value;
+                format!("Pitch set to {multiplier}x")
+            }
+        };
+        (description, session.lavalink_filters())
+    };
+
+    // Send command to Lavalink
+    player.set_filters(filters).await?;
+
+    // Respond in Discord
+    Ok(Response::new().description(&description).build())
+}
@@ -0,0 +1,37 @@
+use super::{get_command_context, get_option};
+use crate::{FerrisError, FerrisResponse, Response, session::get_settings};
+use serenity::{
+    all::{CommandInteraction, ResolvedValue},
+    client::Context,
+};
+
+pub async fn volume(ctx: &Context, interaction: &CommandInteraction) -> FerrisResponse {
+    // Init variables
+    let (guild_id, lava_client, Some(player), _) = get_command_context(ctx, interaction).await?
+    else {
+        Err(FerrisError::LavalinkError)?
+    };
+    let options = interaction.data.options();
+
+    // Show current volume if no level was given
+    let Some(ResolvedValue::Integer(level)) = get_option(&options, "level") else {
+        let volume = player.get_player().await?.volume;
+        return Ok(Response::new()
+            .description(&format!("Volume is {volume}%"))
+            .build());
+    };
+
+    let max_volume = get_settings(&lava_client, guild_id).await?.max_volume;
+    let level = u16::try_from(*level).map_err(|_| FerrisError::VolumeLimitError(max_volume))?;
+    if level > max_volume {
+        Err(FerrisError::VolumeLimitError(max_volume))?
+    }
+
+    // Send command to Lavalink
+    player.set_volume(level).await?;
+
+    // Respond in Discord
+    Ok(Response::new()
+        .description(&format!("Volume set to {level}%"))
+        .build())
+}
@@ -1,70 +1,152 @@
 use crate::{
-    FerrisError, Lavalink, LoopingTrack, Response,
-    commands::{self, get_songbird_manager},
+    FerrisError, FerrisResponse, Lavalink, LavalinkData, MAX_IDLE_MINUTES, MAX_VOLUME, Response,
+    commands::{
+        self, get_songbird_manager,
+        loop_track::{add_loop_copy, remove_loop_copy},
+        timescale::{MAX_TIMESCALE, MIN_TIMESCALE},
+    },
+    filters::{EQ_BANDS, FilterPreset, MAX_GAIN, MIN_GAIN},
+    idle::update_idle,
+    permissions::check_permission,
+    requested_by,
+    session::{LoopMode, get_sessions},
+    sources::SearchSource,
+};
+use lavalink_rs::model::{
+    events::{TrackEnd, TrackEndReason, TrackException, TrackStart, TrackStuck, WebSocketClosed},
+    track::TrackData,
 };
-use lavalink_rs::model::events::TrackStart;
 use lavalink_rs::prelude::LavalinkClient;
 use serenity::{
-    all::{CommandOptionType, Interaction},
+    all::{ChannelType, CommandInteraction, CommandOptionType, Interaction, Permissions},
     async_trait,
     builder::{
-        CreateCommand, CreateCommandOption, CreateInteractionResponse,
-        CreateInteractionResponseMessage,
+        CreateAutocompleteResponse, CreateCommand, CreateCommandOption, CreateEmbed,
+        CreateInteractionResponse, CreateInteractionResponseFollowup,
+        CreateInteractionResponseMessage, CreateMessage, EditInteractionResponse,
     },
     client::{Context, EventHandler},
     framework::standard::macros::hook,
     gateway::ActivityData,
     model::gateway::Ready,
-    model::prelude::VoiceState,
+    model::{id::GuildId, prelude::VoiceState},
 };
-use tokio::sync::RwLock;
+use std::{error::Error, time::Duration};
 use tracing::{info, log::error, warn};
 
+/// How long to wait for the next track to start before announcing that the queue has finished
+const QUEUE_FINISHED_DELAY: Duration = Duration::from_secs(3);
+
 pub struct Handler;
 #[async_trait]
 impl EventHandler for Handler {
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        if let Interaction::Command(command) = interaction {
-            let message_data_result = match command.data.name.as_str() {
-                "help" => commands::help::help(&ctx, &command).await,
-                "join" => commands::join::join(&ctx, &command).await,
-                "play" => commands::play::play(&ctx, &command).await,
-                "leave" => commands::leave::leave(&ctx, &command).await,
-                "stop" => commands::stop::stop(&ctx, &command).await,
-                "loop" => commands::loop_track::loop_track(&ctx, &command).await,
-                "queue" => commands::queue::queue(&ctx, &command).await,
-                "skip" => commands::skip::skip(&ctx, &command).await,
-                "np" => commands::np::now_playing(&ctx, &command).await,
-                "goto" => commands::goto::goto(&ctx, &command).await,
-                _ => Err("No such command".into()),
-            };
-
-            if let Err(why) = command
-                .create_response(
-                    &ctx.http,
-                    CreateInteractionResponse::Message({
-                        CreateInteractionResponseMessage::new().add_embed(
-                            message_data_result.unwrap_or_else(|err| {
-                                let desciption =
-                                    if let Some(error) = err.downcast_ref::<FerrisError>() {
-                                        warn!("{error:#?}");
-                                        error.to_string()
-                                    } else {
-                                        error!("{err:#?}");
-                                        "Something went wrong".to_string()
-                                    };
-                                Response::new()
-                                    .title("Error")
-                                    .description(&desciption)
-                                    .build()
-                            }),
-                        )
-                    }),
-                )
-                .await
-            {
-                error!("Cannot respond to slash command: {}", why);
+        match interaction {
+            Interaction::Command(command) => {
+                // Acknowledge slow commands right away so Discord keeps waiting for the response
+                let deferred = needs_defer(&command.data.name);
+                if deferred && let Err(why) = command.defer(&ctx.http).await {
+                    error!("Cannot defer slash command: {}", why);
+                    return;
+                }
+
+                let response = match check_permission(&ctx, &command).await {
+                    Err(error) => Err(error.into()),
+                    // Search responds with a menu in addition to the embed
+                    Ok(()) if command.data.name == "search" => {
+                        commands::search::search(&ctx, &command).await
+                    }
+                    Ok(()) => run_command(&ctx, &command)
+                        .await
+                        .map(|embed| (embed, vec![])),
+                };
+                let (embed, components) = response.unwrap_or_else(|err| (error_embed(err), vec![]));
+
+                let result = if deferred {
+                    command
+                        .edit_response(
+                            &ctx.http,
+                            EditInteractionResponse::new()
+                                .embed(embed)
+                                .components(components),
+                        )
+                        .await
+                        .map(|_| ())
+                } else {
+                    command
+                        .create_response(
+                            &ctx.http,
+                            CreateInteractionResponse::Message(
+                                CreateInteractionResponseMessage::new()
+                                    .add_embed(embed)
+                                    .components(components),
+                            ),
+                        )
+                        .await
+                };
+                if let Err(why) = result {
+                    error!("Cannot respond to slash command: {}", why);
+                }
             }
+            Interaction::Component(component) => {
+                // Picking queues a track which can be slow, so acknowledge first and edit the message later
+                if let Err(why) = component
+                    .create_response(&ctx.http, CreateInteractionResponse::Acknowledge)
+                    .await
+                {
+                    error!("Cannot acknowledge component: {}", why);
+                    return;
+                }
+
+                let result = match component.data.custom_id.split_once(':') {
+                    Some(("search", _)) => commands::search::pick(&ctx, &component).await,
+                    _ => Err("No such component".into()),
+                };
+
+                // Picking replaces the menu with the result, errors are only shown to the member
+                let result = match result {
+                    Ok(embed) => component
+                        .edit_response(
+                            &ctx.http,
+                            EditInteractionResponse::new()
+                                .embed(embed)
+                                .components(vec![]),
+                        )
+                        .await
+                        .map(|_| ()),
+                    Err(err) => component
+                        .create_followup(
+                            &ctx.http,
+                            CreateInteractionResponseFollowup::new()
+                                .add_embed(error_embed(err))
+                                .ephemeral(true),
+                        )
+                        .await
+                        .map(|_| ()),
+                };
+                if let Err(why) = result {
+                    error!("Cannot respond to component: {}", why);
+                }
+            }
+            Interaction::Autocomplete(autocomplete) => {
+                let result = match autocomplete.data.name.as_str() {
+                    "play" => commands::play::autocomplete(&ctx, &autocomplete).await,
+                    _ => Err("No such autocomplete".into()),
+                };
+
+                // Members can still use what they typed if suggestions fail
+                let response = result.unwrap_or_else(|err| {
+                    warn!("Cannot suggest tracks: {err:#?}");
+                    CreateAutocompleteResponse::new()
+                });
+                if let Err(why) = autocomplete
+                    .create_response(&ctx.http, CreateInteractionResponse::Autocomplete(response))
+                    .await
+                {
+                    error!("Cannot respond to autocomplete: {}", why);
+                }
+            }
+            _ => (),
         }
     }
 
@@ -86,8 +168,26 @@ impl EventHandler for Handler {
                             "url",
                             "url of the track or a search query",
                         )
+                        .set_autocomplete(true)
                         .required(true),
-                    ),
+                    )
+                    .add_option(CreateCommandOption::new(
+                        CommandOptionType::String,
+                        "position",
+                        "next, now or a position in queue, end by default",
+                    ))
+                    .add_option(engine_option()),
+                CreateCommand::new("search")
+                    .description("Searches for tracks and lets you pick one to queue")
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "query",
+                            "what to search for",
+                        )
+                        .required(true),
+                    )
+                    .add_option(engine_option()),
                 CreateCommand::new("goto")
                     .description("Goes to specific point in track")
                     .add_option(
@@ -100,10 +200,348 @@ impl EventHandler for Handler {
                     ),
                 CreateCommand::new("stop").description("Stops current track and clears queue"),
                 CreateCommand::new("queue").description("Displays current queue"),
-                CreateCommand::new("skip").description("Skips current song"),
+                CreateCommand::new("skip").description("Skips current song or votes to skip it"),
+                CreateCommand::new("skipto")
+                    .description("Skips to a position in queue")
+                    .add_option(queue_index_option(
+                        "position",
+                        "position of the track to play",
+                    ))
+                    .add_option(CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "keep",
+                        "move skipped tracks to the end of queue instead of removing them",
+                    )),
+                CreateCommand::new("previous").description("Plays previous track again"),
+                CreateCommand::new("replay").description("Restarts current track"),
+                CreateCommand::new("history").description("Displays recently played tracks"),
+                CreateCommand::new("remove")
+                    .description("Removes tracks from queue")
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "position",
+                            "position in queue or a range like 2-5",
+                        )
+                        .required(true),
+                    ),
+                CreateCommand::new("move")
+                    .description("Moves a track to another position in queue")
+                    .add_option(queue_index_option("from", "current position of the track"))
+                    .add_option(queue_index_option("to", "new position of the track")),
+                CreateCommand::new("swap")
+                    .description("Swaps positions of two tracks in queue")
+                    .add_option(queue_index_option("a", "position of the first track"))
+                    .add_option(queue_index_option("b", "position of the second track")),
+                CreateCommand::new("shuffle")
+                    .description("Shuffles queue")
+                    .add_option(CreateCommandOption::new(
+                        CommandOptionType::Boolean,
+                        "mode",
+                        "keep adding new tracks to random positions",
+                    )),
+                CreateCommand::new("reverse").description("Reverses queue"),
+                CreateCommand::new("sort")
+                    .description("Sorts queue")
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::String,
+                            "by",
+                            "what to sort by",
+                        )
+                        .add_string_choice("title", "title")
+                        .add_string_choice("duration", "duration")
+                        .add_string_choice("requester", "requester")
+                        .add_string_choice("source", "source")
+                        .required(true),
+                    ),
                 CreateCommand::new("help").description("Displays help"),
-                CreateCommand::new("loop").description("Loops current track"),
+                CreateCommand::new("loop")
+                    .description("Loops current track or the whole queue")
+                    .add_option(
+                        CreateCommandOption::new(CommandOptionType::String, "mode", "what to loop")
+                            .add_string_choice("off", "off")
+                            .add_string_choice("track", "track")
+                            .add_string_choice("queue", "queue")
+                            .required(true),
+                    ),
                 CreateCommand::new("np").description("Displays info on currently playing track"),
+                CreateCommand::new("pause").description("Pauses current track"),
+                CreateCommand::new("resume").description("Resumes current track"),
+                CreateCommand::new("playpause").description("Pauses or resumes current track"),
+                CreateCommand::new("volume")
+                    .description("Changes or displays volume")
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::Integer,
+                            "level",
+                            "volume as percentage",
+                        )
+                        .min_int_value(0)
+                        .max_int_value(MAX_VOLUME.into()),
+                    ),
+                CreateCommand::new("filter")
+                    .description("Adds or removes audio filters")
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "add",
+                            "Adds a filter on top of active filters",
+                        )
+                        .add_sub_option(filter_preset_option()),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "remove",
+                            "Removes an active filter",
+                        )
+                        .add_sub_option(filter_preset_option()),
+                    )
+                    .add_option(CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "reset",
+                        "Removes all filters",
+                    )),
+                CreateCommand::new("eq")
+                    .description("Adjusts equalizer and its saved profiles")
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "set",
+                            "Sets gain of a band",
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Integer,
+                                "band",
+                                "band from 0 (25 Hz) to 14 (16 kHz)",
+                            )
+                            .min_int_value(0)
+                            .max_int_value(EQ_BANDS as u64 - 1)
+                            .required(true),
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Number,
+                                "gain",
+                                "gain from -0.25 (muted) to 1.0",
+                            )
+                            .min_number_value(MIN_GAIN)
+                            .max_number_value(MAX_GAIN)
+                            .required(true),
+                        ),
+                    )
+                    .add_option(CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "reset",
+                        "Sets all bands to zero",
+                    ))
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "save",
+                            "Saves equalizer as a profile",
+                        )
+                        .add_sub_option(eq_profile_option()),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "load",
+                            "Loads a saved profile",
+                        )
+                        .add_sub_option(eq_profile_option()),
+                    )
+                    .add_option(CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "list",
+                        "Lists saved profiles",
+                    )),
+                CreateCommand::new("speed")
+                    .description("Changes playback speed")
+                    .add_option(timescale_option()),
+                CreateCommand::new("pitch")
+                    .description("Changes pitch")
+                    .add_option(timescale_option()),
+                CreateCommand::new("settings")
+                    .description("Changes settings of the server")
+                    .default_member_permissions(Permissions::MANAGE_GUILD)
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "volume",
+                            "Changes default and maximum volume",
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Integer,
+                                "default",
+                                "volume the player starts with",
+                            )
+                            .min_int_value(0)
+                            .max_int_value(MAX_VOLUME.into()),
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Integer,
+                                "max",
+                                "highest volume members can set",
+                            )
+                            .min_int_value(0)
+                            .max_int_value(MAX_VOLUME.into()),
+                        ),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "idle",
+                            "Changes how long Ferris stays when alone or idle",
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Integer,
+                                "minutes",
+                                "minutes before leaving",
+                            )
+                            .min_int_value(1)
+                            .max_int_value(MAX_IDLE_MINUTES)
+                            .required(true),
+                        ),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "voteskip",
+                            "Changes whether skipping needs votes from listeners",
+                        )
+                        .add_sub_option(CreateCommandOption::new(
+                            CommandOptionType::Boolean,
+                            "enabled",
+                            "whether skipping needs votes",
+                        ))
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Number,
+                                "ratio",
+                                "share of listeners needed, 0.5 is half",
+                            )
+                            .min_number_value(0.0)
+                            .max_number_value(1.0),
+                        ),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "dj",
+                            "Changes which role can control the player",
+                        )
+                        .add_sub_option(CreateCommandOption::new(
+                            CommandOptionType::Role,
+                            "role",
+                            "DJ role, everyone can control the player if not given",
+                        )),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "fairqueue",
+                            "Changes whether requesters take turns in queue",
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Boolean,
+                                "enabled",
+                                "whether requesters take turns",
+                            )
+                            .required(true),
+                        ),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "source",
+                            "Changes where tracks are searched from by default",
+                        )
+                        .add_sub_option(engine_option().required(true)),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "fallback",
+                            "Changes which sources are tried in order when a search finds nothing",
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::String,
+                                "sources",
+                                "sources separated by commas like youtubemusic, soundcloud or none",
+                            )
+                            .required(true),
+                        ),
+                    )
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "limits",
+                            "Changes limits of the queue, 0 removes a limit",
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Integer,
+                                "user_tracks",
+                                "most tracks a member can have in queue",
+                            )
+                            .min_int_value(0),
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Integer,
+                                "queue_length",
+                                "most tracks in queue",
+                            )
+                            .min_int_value(0),
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Integer,
+                                "track_minutes",
+                                "longest track that can be queued in minutes",
+                            )
+                            .min_int_value(0),
+                        )
+                        .add_sub_option(CreateCommandOption::new(
+                            CommandOptionType::Boolean,
+                            "streams",
+                            "whether live streams can be queued",
+                        )),
+                    ),
+                CreateCommand::new("247")
+                    .description("Toggles staying on voice channel even when alone or idle")
+                    .default_member_permissions(Permissions::MANAGE_GUILD),
+                CreateCommand::new("announce")
+                    .description("Changes where now playing and other messages are posted")
+                    .default_member_permissions(Permissions::MANAGE_GUILD)
+                    .add_option(
+                        CreateCommandOption::new(
+                            CommandOptionType::SubCommand,
+                            "channel",
+                            "Posts messages to a channel",
+                        )
+                        .add_sub_option(
+                            CreateCommandOption::new(
+                                CommandOptionType::Channel,
+                                "channel",
+                                "channel to post to, channel Ferris was summoned from by default",
+                            )
+                            .channel_types(vec![ChannelType::Text]),
+                        ),
+                    )
+                    .add_option(CreateCommandOption::new(
+                        CommandOptionType::SubCommand,
+                        "off",
+                        "Stops posting messages",
+                    )),
             ],
         )
         .await
@@ -126,28 +564,373 @@ impl EventHandler for Handler {
                 error!("Removing manager failed with error: {}", e)
             }
 
-            ctx.data
-                .read()
-                .await
+            let data = ctx.data.read().await;
+            let lava_client = data
                 .get::<Lavalink>()
-                .expect("Expected to have lavalink client in voice state update")
+                .expect("Expected to have lavalink client in voice state update");
+            lava_client
                 .delete_player(guild_id.get())
                 .await
-                .expect("Failed to delete player")
+                .expect("Failed to delete player");
+
+            // Session ends when Ferris leaves the voice channel
+            if let Ok(sessions) = get_sessions(lava_client)
+                && let Some(session) = sessions.write().await.get_mut(&guild_id)
+            {
+                session.end();
+            }
+        }
+        // Members joining or leaving may leave Ferris alone or bring company
+        else if let Some(guild_id) = new.guild_id {
+            let data = ctx.data.read().await;
+            if let Some(lava_client) = data.get::<Lavalink>() {
+                update_idle(lava_client, guild_id).await;
+            }
         }
     }
 }
 
+/// Whether a command may take longer than Discord waits for a response, like commands that join a
+/// voice channel or load tracks. Responses of these commands are deferred and edited in when ready.
+fn needs_defer(name: &str) -> bool {
+    matches!(name, "join" | "play" | "search")
+}
+
+/// Run a slash command by its name
+async fn run_command(ctx: &Context, command: &CommandInteraction) -> FerrisResponse {
+    match command.data.name.as_str() {
+        "help" => commands::help::help(ctx, command).await,
+        "join" => commands::join::join(ctx, command).await,
+        "play" => commands::play::play(ctx, command).await,
+        "leave" => commands::leave::leave(ctx, command).await,
+        "stop" => commands::stop::stop(ctx, command).await,
+        "loop" => commands::loop_track::loop_track(ctx, command).await,
+        "queue" => commands::queue::queue(ctx, command).await,
+        "skip" => commands::skip::skip(ctx, command).await,
+        "skipto" => commands::skip_to::skip_to(ctx, command).await,
+        "previous" => commands::history::previous(ctx, command).await,
+        "replay" => commands::replay::replay(ctx, command).await,
+        "history" => commands::history::history(ctx, command).await,
+        "np" => commands::np::now_playing(ctx, command).await,
+        "pause" => commands::pause::pause(ctx, command).await,
+        "resume" => commands::pause::resume(ctx, command).await,
+        "playpause" => commands::pause::toggle_pause(ctx, command).await,
+        "volume" => commands::volume::volume(ctx, command).await,
+        "settings" => commands::settings::settings(ctx, command).await,
+        "announce" => commands::announce::announce(ctx, command).await,
+        "247" => commands::always_on::always_on(ctx, command).await,
+        "filter" => commands::filter::filter(ctx, command).await,
+        "eq" => commands::eq::eq(ctx, command).await,
+        "speed" => commands::timescale::speed(ctx, command).await,
+        "pitch" => commands::timescale::pitch(ctx, command).await,
+        "goto" => commands::goto::goto(ctx, command).await,
+        "remove" => commands::remove::remove(ctx, command).await,
+        "move" => commands::move_track::move_track(ctx, command).await,
+        "swap" => commands::swap::swap(ctx, command).await,
+        "shuffle" => commands::shuffle::shuffle(ctx, command).await,
+        "reverse" => commands::reverse::reverse(ctx, command).await,
+        "sort" => commands::sort::sort(ctx, command).await,
+        _ => Err("No such command".into()),
+    }
+}
+
+/// Turn an error into a message, only errors of Ferris are shown to members
+fn error_embed(err: Box<dyn Error + Sync + Send>) -> CreateEmbed {
+    let desciption = if let Some(error) = err.downcast_ref::<FerrisError>() {
+        warn!("{error:#?}");
+        error.to_string()
+    } else {
+        error!("{err:#?}");
+        "Something went wrong".to_string()
+    };
+    Response::new()
+        .title("Error")
+        .description(&desciption)
+        .build()
+}
+
+/// Option for a position in queue, numbered like in queue command
+fn queue_index_option(name: &str, description: &str) -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::Integer, name, description)
+        .min_int_value(0)
+        .required(true)
+}
+
+/// Option for choosing where to search for tracks
+fn engine_option() -> CreateCommandOption {
+    SearchSource::ALL.into_iter().fold(
+        CreateCommandOption::new(
+            CommandOptionType::String,
+            "engine",
+            "where to search, server default if not given",
+        ),
+        |option, source| option.add_string_choice(source.to_string(), source.name()),
+    )
+}
+
+/// Option for choosing one of the filter presets
+fn filter_preset_option() -> CreateCommandOption {
+    FilterPreset::ALL.into_iter().fold(
+        CreateCommandOption::new(CommandOptionType::String, "preset", "name of the filter")
+            .required(true),
+        |option, preset| option.add_string_choice(preset.to_string(), preset.name()),
+    )
+}
+
+/// Option for naming an equalizer profile
+fn eq_profile_option() -> CreateCommandOption {
+    CreateCommandOption::new(CommandOptionType::String, "name", "name of the profile")
+        .max_length(32)
+        .required(true)
+}
+
+/// Option for speed or pitch multiplier
+fn timescale_option() -> CreateCommandOption {
+    CreateCommandOption::new(
+        CommandOptionType::Number,
+        "multiplier",
+        "1.0 is normal, 0.5 is half and 2.0 is double",
+    )
+    .min_number_value(MIN_TIMESCALE)
+    .max_number_value(MAX_TIMESCALE)
+    .required(true)
+}
+
 #[hook]
 pub async fn track_start(client: LavalinkClient, _session_id: String, event: &TrackStart) {
-    let mutex = client.data::<RwLock<Option<LoopingTrack>>>().unwrap();
-    let data = mutex.read().await;
-    let player_context = client.get_player_context(event.guild_id);
-
-    if let (Some(player), Some(looping_track)) = (player_context, &*data) {
-        player
-            .get_queue()
-            .push_to_front(looping_track.0.clone())
-            .unwrap_or(());
+    let guild_id = GuildId::new(event.guild_id.0);
+    let sessions = get_sessions(&client).expect("Expected to have sessions in lavalink client");
+    let (loop_mode, repeating) = sessions
+        .write()
+        .await
+        .get_mut(&guild_id)
+        .map(|session| {
+            // Votes to skip were for the previous track
+            session.skip_votes.clear();
+            session.vote_skipped = false;
+            (
+                session.loop_mode,
+                session
+                    .history
+                    .back()
+                    .is_some_and(|last| last.encoded == event.track.encoded),
+            )
+        })
+        .unwrap_or_default();
+
+    // Requeue the track that just started if we are looping
+    if let Some(player) = client.get_player_context(event.guild_id) {
+        add_loop_copy(&player, loop_mode, event.track.clone()).unwrap_or(());
+    }
+    update_idle(&client, guild_id).await;
+
+    // Don't announce the same track again every time it loops
+    if loop_mode == LoopMode::Track && repeating {
+        return;
+    }
+    let title = match &event.track.info.uri {
+        Some(uri) => format!("[{}]({uri})", event.track.info.title),
+        None => event.track.info.title.clone(),
+    };
+    announce(
+        &client,
+        guild_id,
+        Response::new()
+            .title("Now playing")
+            .description(&format!("{title}{}", requested_by(&event.track)))
+            .build(),
+    )
+    .await;
+}
+
+#[hook]
+pub async fn track_end(client: LavalinkClient, _session_id: String, event: &TrackEnd) {
+    let guild_id = GuildId::new(event.guild_id.0);
+
+    // Tracks that failed or were cleaned up never really played
+    if !matches!(
+        event.reason,
+        TrackEndReason::LoadFailed | TrackEndReason::Cleanup
+    ) {
+        let sessions = get_sessions(&client).expect("Expected to have sessions in lavalink client");
+        if let Some(session) = sessions.write().await.get_mut(&guild_id) {
+            session.record_history(event.track.clone());
+        }
+    }
+
+    // Next track starts only after this handler, so check again once it has had time to start
+    if matches!(
+        event.reason,
+        TrackEndReason::Finished | TrackEndReason::LoadFailed
+    ) {
+        let client = client.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(QUEUE_FINISHED_DELAY).await;
+            if let Some(player) = client.get_player_context(guild_id.get())
+                && player
+                    .get_queue()
+                    .get_count()
+                    .await
+                    .is_ok_and(|count| count == 0)
+                && player
+                    .get_player()
+                    .await
+                    .is_ok_and(|player| player.track.is_none())
+            {
+                announce(
+                    &client,
+                    guild_id,
+                    Response::new()
+                        .description("Queue finished, add more with /play")
+                        .build(),
+                )
+                .await;
+            }
+        });
+    }
+
+    // Track start clears idle state again if another track started
+    update_idle(&client, guild_id).await;
+}
+
+#[hook]
+pub async fn track_exception(client: LavalinkClient, _session_id: String, event: &TrackException) {
+    let guild_id = GuildId::new(event.guild_id.0);
+    warn!(
+        "Track {} failed in guild {guild_id} with {} exception: {} caused by {}",
+        event.track.info.title,
+        event.exception.severity,
+        event.exception.message,
+        event.exception.cause
+    );
+
+    // Lavalink ends the track after an exception so it is skipped already
+    skip_broken_track(&client, guild_id, &event.track, false).await;
+    announce(
+        &client,
+        guild_id,
+        Response::new()
+            .title("Could not play track")
+            .description(&format!(
+                "Skipped {}: {}",
+                event.track.info.title, event.exception.message
+            ))
+            .build(),
+    )
+    .await;
+}
+
+#[hook]
+pub async fn track_stuck(client: LavalinkClient, _session_id: String, event: &TrackStuck) {
+    let guild_id = GuildId::new(event.guild_id.0);
+    warn!(
+        "Track {} got stuck in guild {guild_id} for over {} ms",
+        event.track.info.title, event.threshold_ms
+    );
+
+    skip_broken_track(&client, guild_id, &event.track, true).await;
+    announce(
+        &client,
+        guild_id,
+        Response::new()
+            .title("Track got stuck")
+            .description(&format!(
+                "Skipped {} as it stopped playing",
+                event.track.info.title
+            ))
+            .build(),
+    )
+    .await;
+}
+
+#[hook]
+pub async fn websocket_closed(
+    client: LavalinkClient,
+    _session_id: String,
+    event: &WebSocketClosed,
+) {
+    let guild_id = GuildId::new(event.guild_id.0);
+    warn!(
+        "Voice connection closed in guild {guild_id} with code {}: {}",
+        event.code, event.reason
+    );
+
+    // Normal closing and disconnecting from the channel are handled in voice state update
+    if !matches!(event.code, 1000 | 4014) {
+        announce(
+            &client,
+            guild_id,
+            Response::new()
+                .title("Lost connection to voice channel")
+                .description(&format!("{} ({})", event.reason, event.code))
+                .build(),
+        )
+        .await;
+    }
+}
+
+/// Make sure a track that failed isn't played again by looping and optionally skip it
+///
+/// # Arguments
+///
+/// * `client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `track` - track that failed
+/// * `skip` - whether to skip the track
+///
+async fn skip_broken_track(
+    client: &LavalinkClient,
+    guild_id: GuildId,
+    track: &TrackData,
+    skip: bool,
+) {
+    let Some(player) = client.get_player_context(guild_id.get()) else {
+        return;
+    };
+    let loop_mode = match get_sessions(client) {
+        Ok(sessions) => sessions
+            .read()
+            .await
+            .get(&guild_id)
+            .map(|session| session.loop_mode)
+            .unwrap_or_default(),
+        Err(_) => Default::default(),
+    };
+
+    if let Err(e) = remove_loop_copy(&player, loop_mode, track).await {
+        error!("Removing looping copy of broken track failed with error: {e}");
+    }
+    if skip && let Err(e) = player.skip() {
+        error!("Skipping broken track failed with error: {e}");
+    }
+}
+
+/// Post a message to the announcement channel of the guild, unless announcements are off
+///
+/// # Arguments
+///
+/// * `client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `embed` - message to post
+///
+pub async fn announce(client: &LavalinkClient, guild_id: GuildId, embed: CreateEmbed) {
+    let Ok(data) = client.data::<LavalinkData>() else {
+        return;
+    };
+    let Some(channel_id) = data
+        .sessions
+        .read()
+        .await
+        .get(&guild_id)
+        .and_then(|session| session.announce_channel())
+    else {
+        return;
+    };
+
+    if let Err(why) = channel_id
+        .send_message(&data.http, CreateMessage::new().embed(embed))
+        .await
+    {
+        error!("Cannot announce in guild {guild_id}: {why}");
     }
 }
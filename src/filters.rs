@@ -0,0 +1,218 @@
+use lavalink_rs::model::player::{Equalizer, Filters, Karaoke, Rotation, Timescale};
+use std::fmt::{Display, Write};
+use tracing::log::warn;
+
+/// Amount of equalizer bands in Lavalink
+pub const EQ_BANDS: usize = 15;
+pub const MIN_GAIN: f64 = -0.25;
+pub const MAX_GAIN: f64 = 1.0;
+/// Center frequencies of the equalizer bands in Hz
+const EQ_FREQUENCIES: [u32; EQ_BANDS] = [
+    25, 40, 63, 100, 160, 250, 400, 630, 1000, 1600, 2500, 4000, 6300, 10000, 16000,
+];
+/// Gain of a single character in equalizer visualisation
+const GAIN_PER_CHAR: f64 = 0.05;
+
+/// Gains of all equalizer bands
+pub type EqualizerBands = [f64; EQ_BANDS];
+
+/// Named audio filter presets. Presets can be stacked, in which case later presets override
+/// the parts of earlier ones that use the same Lavalink filter. Equalizer gains are summed instead.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FilterPreset {
+    BassBoost,
+    Nightcore,
+    Vaporwave,
+    EightD,
+    Karaoke,
+}
+
+impl FilterPreset {
+    pub const ALL: [FilterPreset; 5] = [
+        FilterPreset::BassBoost,
+        FilterPreset::Nightcore,
+        FilterPreset::Vaporwave,
+        FilterPreset::EightD,
+        FilterPreset::Karaoke,
+    ];
+
+    /// Name of the preset used in commands
+    pub fn name(&self) -> &'static str {
+        match self {
+            FilterPreset::BassBoost => "bassboost",
+            FilterPreset::Nightcore => "nightcore",
+            FilterPreset::Vaporwave => "vaporwave",
+            FilterPreset::EightD => "8d",
+            FilterPreset::Karaoke => "karaoke",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+
+    /// Add Lavalink filters of the preset to `filters`. Equalizer gains are added on top of `gains`.
+    fn apply(&self, filters: &mut Filters, gains: &mut EqualizerBands) {
+        match self {
+            FilterPreset::BassBoost => {
+                for (gain, boost) in gains.iter_mut().zip([0.25, 0.2, 0.15, 0.1, 0.05]) {
+                    *gain += boost;
+                }
+            }
+            FilterPreset::Nightcore => {
+                filters.timescale = Some(Timescale {
+                    speed: Some(1.2),
+                    pitch: Some(1.2),
+                    rate: None,
+                })
+            }
+            FilterPreset::Vaporwave => {
+                filters.timescale = Some(Timescale {
+                    speed: Some(0.85),
+                    pitch: Some(0.8),
+                    rate: None,
+                })
+            }
+            FilterPreset::EightD => {
+                filters.rotation = Some(Rotation {
+                    rotation_hz: Some(0.2),
+                })
+            }
+            FilterPreset::Karaoke => {
+                filters.karaoke = Some(Karaoke {
+                    level: Some(1.0),
+                    mono_level: Some(1.0),
+                    filter_band: Some(220.0),
+                    filter_width: Some(100.0),
+                })
+            }
+        }
+    }
+}
+
+impl Display for FilterPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            FilterPreset::BassBoost => write!(f, "Bass boost"),
+            FilterPreset::Nightcore => write!(f, "Nightcore"),
+            FilterPreset::Vaporwave => write!(f, "Vaporwave"),
+            FilterPreset::EightD => write!(f, "8D"),
+            FilterPreset::Karaoke => write!(f, "Karaoke"),
+        }
+    }
+}
+
+/// Combine custom equalizer, speed, pitch and stacked presets into Lavalink filters
+///
+/// # Arguments
+/// * `presets` - active presets in the order they were added
+/// * `equalizer` - custom equalizer gains that presets are added on top of
+/// * `speed` - custom speed multiplier applied on top of presets
+/// * `pitch` - custom pitch multiplier applied on top of presets
+///
+/// # Examples
+/// ```
+/// use dj_ferris::filters::{EQ_BANDS, FilterPreset, build_filters};
+///
+/// let presets = [FilterPreset::Nightcore, FilterPreset::EightD];
+/// let filters = build_filters(&presets, &[0.0; EQ_BANDS], Some(0.5), None);
+///
+/// assert!(filters.rotation.is_some());
+/// assert!(filters.equalizer.is_none());
+/// assert_eq!(filters.timescale.unwrap().speed, Some(0.6));
+/// # assert_eq!(build_filters(&[], &[0.0; EQ_BANDS], None, None), Default::default());
+/// # let mut bands = [0.0; EQ_BANDS];
+/// # bands[0] = 0.9;
+/// # let equalizer = build_filters(&[FilterPreset::BassBoost], &bands, None, None).equalizer.unwrap();
+/// # assert_eq!(equalizer[0].gain, 1.0);
+/// # assert_eq!(equalizer[1].gain, 0.2);
+/// ```
+pub fn build_filters(
+    presets: &[FilterPreset],
+    equalizer: &EqualizerBands,
+    speed: Option<f64>,
+    pitch: Option<f64>,
+) -> Filters {
+    let mut filters = Filters::default();
+    let mut gains = *equalizer;
+    for preset in presets {
+        preset.apply(&mut filters, &mut gains);
+    }
+    if speed.is_some() || pitch.is_some() {
+        let timescale = filters.timescale.get_or_insert_default();
+        if let Some(speed) = speed {
+            timescale.speed = Some(timescale.speed.unwrap_or(1.0) * speed);
+        }
+        if let Some(pitch) = pitch {
+            timescale.pitch = Some(timescale.pitch.unwrap_or(1.0) * pitch);
+        }
+    }
+    if gains.iter().any(|gain| *gain != 0.0) {
+        filters.equalizer = Some(
+            gains
+                .iter()
+                .enumerate()
+                .map(|(band, gain)| Equalizer {
+                    band: band as u8,
+                    gain: gain.clamp(MIN_GAIN, MAX_GAIN),
+                })
+                .collect(),
+        );
+    }
+    filters
+}
+
+/// How many times faster than normal the filters make tracks play
+///
+/// # Examples
+/// ```
+/// use dj_ferris::filters::{EQ_BANDS, FilterPreset, build_filters, playback_speed};
+///
+/// let filters = build_filters(&[FilterPreset::Vaporwave], &[0.0; EQ_BANDS], Some(2.0), None);
+///
+/// assert_eq!(playback_speed(&filters), 1.7);
+/// # assert_eq!(playback_speed(&Default::default()), 1.0);
+/// ```
+pub fn playback_speed(filters: &Filters) -> f64 {
+    filters.timescale.as_ref().map_or(1.0, |timescale| {
+        timescale.speed.unwrap_or(1.0) * timescale.rate.unwrap_or(1.0)
+    })
+}
+
+/// Draw equalizer gains as a bar for each band
+///
+/// # Arguments
+/// * `equalizer` - gains of all equalizer bands
+///
+/// # Examples
+/// ```
+/// use dj_ferris::filters::{EQ_BANDS, render_equalizer};
+///
+/// let mut bands = [0.0; EQ_BANDS];
+/// bands[0] = 0.25;
+/// bands[1] = -0.1;
+/// let visualisation = render_equalizer(&bands);
+///
+/// let mut lines = visualisation.lines();
+/// assert_eq!(lines.next(), Some("   25 Hz      |█████                +0.25"));
+/// assert_eq!(lines.next(), Some("   40 Hz    ██|                     -0.10"));
+/// assert_eq!(lines.next(), Some("   63 Hz      |                     +0.00"));
+/// # assert_eq!(visualisation.lines().count(), EQ_BANDS);
+/// ```
+pub fn render_equalizer(equalizer: &EqualizerBands) -> String {
+    let mut visualisation = String::new();
+    for (frequency, gain) in EQ_FREQUENCIES.iter().zip(equalizer) {
+        let bar = "█".repeat((gain.abs() / GAIN_PER_CHAR).round() as usize);
+        let (negative, positive) = if *gain < 0.0 {
+            (bar.as_str(), "")
+        } else {
+            ("", bar.as_str())
+        };
+        writeln!(
+            visualisation,
+            "{frequency:>5} Hz {negative:>5}|{positive:<20} {gain:+.2}"
+        )
+        .unwrap_or_else(|_| warn!("Could not write to equalizer visualisation"));
+    }
+    visualisation
+}
@@ -0,0 +1,159 @@
+use crate::{LavalinkData, Response, events::announce, get_listeners};
+use lavalink_rs::client::LavalinkClient;
+use serenity::model::id::GuildId;
+use std::time::{Duration, Instant};
+use tracing::log::error;
+
+/// Why Ferris isn't in use
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Idle {
+    /// No one else is on the voice channel
+    Alone,
+    /// Nothing is playing
+    NotPlaying,
+}
+
+/// Check whether Ferris is in use in a guild and leave the voice channel after the idle timeout if it isn't
+///
+/// Call this whenever members move between voice channels or playback starts or stops.
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+///
+pub async fn update_idle(lava_client: &LavalinkClient, guild_id: GuildId) {
+    let Ok(data) = lava_client.data::<LavalinkData>() else {
+        return;
+    };
+    let idle = get_idle(lava_client, &data, guild_id).await;
+
+    // Mark the session idle, only the first check starts a timer
+    let (timer, timeout) = {
+        let mut sessions = data.sessions.write().await;
+        let Some(session) = sessions.get_mut(&guild_id) else {
+            return;
+        };
+        if idle.is_none() || session.settings.always_on {
+            session.idle_since = None;
+            return;
+        }
+        if session.idle_since.is_some() {
+            return;
+        }
+        session.idle_since = Some(Instant::now());
+        session.idle_timer += 1;
+        (session.idle_timer, session.settings.idle_timeout)
+    };
+
+    let lava_client = lava_client.clone();
+    tokio::spawn(async move {
+        let mut remaining = timeout;
+        loop {
+            tokio::time::sleep(remaining).await;
+            match remaining_idle_time(&lava_client, guild_id, timer).await {
+                Some(time) if time.is_zero() => break,
+                Some(time) => remaining = time,
+                None => return,
+            }
+        }
+
+        // Make sure no event was missed while waiting
+        let Ok(data) = lava_client.data::<LavalinkData>() else {
+            return;
+        };
+        let Some(idle) = get_idle(&lava_client, &data, guild_id).await else {
+            if let Some(session) = data.sessions.write().await.get_mut(&guild_id)
+                && session.idle_timer == timer
+            {
+                session.idle_since = None;
+            }
+            return;
+        };
+
+        // A newer timer takes over if Ferris was in use in between
+        if data
+            .sessions
+            .read()
+            .await
+            .get(&guild_id)
+            .is_none_or(|session| session.idle_timer != timer)
+        {
+            return;
+        }
+        leave(&lava_client, &data, guild_id, idle).await;
+    });
+}
+
+/// Time left until Ferris leaves, or None if Ferris is no longer idle or a newer timer has replaced this one
+///
+/// # Arguments
+///
+/// * `lava_client` - Lavalink client
+/// * `guild_id` - ID of the Discord server
+/// * `timer` - number of the idle timer asking
+///
+async fn remaining_idle_time(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+    timer: u64,
+) -> Option<Duration> {
+    let data = lava_client.data::<LavalinkData>().ok()?;
+    let sessions = data.sessions.read().await;
+    let session = sessions.get(&guild_id)?;
+    if session.settings.always_on || session.idle_timer != timer {
+        return None;
+    }
+    let idle_since = session.idle_since?;
+    Some(
+        session
+            .settings
+            .idle_timeout
+            .saturating_sub(idle_since.elapsed()),
+    )
+}
+
+/// Find out why Ferris isn't in use, or None if it is in use or not connected at all
+async fn get_idle(
+    lava_client: &LavalinkClient,
+    data: &LavalinkData,
+    guild_id: GuildId,
+) -> Option<Idle> {
+    let player = lava_client.get_player_context(guild_id.get())?;
+
+    let alone = get_listeners(&data.cache, guild_id).is_none_or(|listeners| listeners.is_empty());
+    if alone {
+        return Some(Idle::Alone);
+    }
+
+    match player.get_player().await {
+        Ok(player) if player.track.is_none() => Some(Idle::NotPlaying),
+        _ => None,
+    }
+}
+
+/// Leave the voice channel and end the session
+async fn leave(lava_client: &LavalinkClient, data: &LavalinkData, guild_id: GuildId, idle: Idle) {
+    let reason = match idle {
+        Idle::Alone => "nobody was listening",
+        Idle::NotPlaying => "nothing was playing",
+    };
+    announce(
+        lava_client,
+        guild_id,
+        Response::new()
+            .description(&format!("Left voice channel as {reason}"))
+            .build(),
+    )
+    .await;
+
+    if let Err(e) = data.songbird.remove(guild_id).await {
+        error!("Leaving idle voice channel failed with error: {e}");
+    }
+    if let Err(e) = lava_client.delete_player(guild_id.get()).await {
+        error!("Deleting idle player failed with error: {e}");
+    }
+    if let Some(session) = data.sessions.write().await.get_mut(&guild_id) {
+        session.end();
+    }
+}
@@ -1,23 +1,64 @@
 use lavalink_rs::client::LavalinkClient;
 use lavalink_rs::model::track::TrackData;
 use lavalink_rs::player_context::QueueRef;
+use permissions::CommandPermission;
+use serenity::cache::Cache;
+use serenity::http::Http;
 use serenity::model::Colour;
-use serenity::prelude::TypeMapKey;
-use serenity::{builder::CreateEmbed, client::Context, model::id::GuildId};
-use std::{cmp::Ordering, time::Duration};
+use serenity::prelude::{Mentionable, TypeMapKey};
+use serenity::{
+    builder::CreateEmbed,
+    client::Context,
+    model::{
+        guild::Guild,
+        id::{GuildId, InteractionId, UserId},
+        voice::VoiceState,
+    },
+};
+use session::Sessions;
+use songbird::Songbird;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    ops::RangeInclusive,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 use std::{
     error::Error,
     fmt::{Display, Write},
 };
+use tokio::sync::Mutex;
 use tracing::log::warn;
 
 //Modules
 pub mod commands;
 pub mod events;
+pub mod filters;
+pub mod idle;
+pub mod permissions;
+pub mod session;
+pub mod sources;
 
 // Constants
 pub const EMBED_COLOR: Colour = Colour::ORANGE;
 pub const MAX_DESCRIPTION_SIZE: usize = 4094;
+pub const DEFAULT_VOLUME: u16 = 100;
+pub const MAX_VOLUME: u16 = 1000;
+pub const MAX_EQ_PROFILES: usize = 25;
+pub const MAX_HISTORY: usize = 50;
+pub const SEARCH_RESULTS: usize = 5;
+/// How long search results can be picked from
+pub const SEARCH_LIFETIME: Duration = Duration::from_secs(5 * 60);
+/// Longest text Discord accepts in select menu options and autocomplete choices
+pub const MAX_OPTION_LENGTH: usize = 100;
+/// How long Ferris stays when alone or idle, unless the guild has set otherwise
+pub const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+pub const MAX_IDLE_MINUTES: u64 = 120;
+/// Share of listeners needed to skip a track when vote skipping
+pub const DEFAULT_VOTE_SKIP_RATIO: f64 = 0.5;
+/// Key of the requesting member in user data of a track
+const REQUESTER_KEY: &str = "requester";
 
 // Structs
 pub struct Lavalink;
@@ -26,6 +67,24 @@ impl TypeMapKey for Lavalink {
     type Value = LavalinkClient;
 }
 
+/// Data stored in Lavalink client so that Lavalink event handlers can reach guild sessions and Discord
+pub struct LavalinkData {
+    pub sessions: Arc<Sessions>,
+    pub http: Arc<Http>,
+    pub cache: Arc<Cache>,
+    pub songbird: Arc<Songbird>,
+    pub suggestions: Mutex<Suggestions>,
+}
+
+/// State of autocomplete suggestions shared by all guilds
+#[derive(Default)]
+pub struct Suggestions {
+    /// Search results by query with the time they were loaded
+    pub results: HashMap<String, (Instant, Vec<TrackData>)>,
+    /// Autocomplete request each member has waiting, older requests are dropped
+    pub latest: HashMap<UserId, InteractionId>,
+}
+
 pub type FerrisResponse = Result<CreateEmbed, Box<dyn Error + Sync + Send>>;
 
 /// Struct for errors that should be returned as a message on Discord
@@ -34,11 +93,26 @@ pub enum FerrisError {
     LavalinkError,
     TrackNotFoundError,
     QueueEmptyError,
+    HistoryEmptyError,
     PositionError,
+    QueueIndexError,
     AlreadyOnCallError,
     NotOnCallError,
     GuildError,
     MissingArguments,
+    VolumeLimitError(u16),
+    ProfileNotFoundError,
+    ProfileLimitError,
+    NotListeningError,
+    PermissionError(CommandPermission),
+    UserTrackLimitError(usize),
+    QueueLengthLimitError(usize),
+    TrackDurationLimitError(u64),
+    StreamLimitError,
+    SearchExpiredError,
+    SearchOwnerError,
+    TrackLoadError(String),
+    UnknownSourceError(String),
 }
 impl Error for FerrisError {}
 impl Display for FerrisError {
@@ -46,21 +120,56 @@ impl Display for FerrisError {
         match self {
             FerrisError::LavalinkError => write!(f, "Someting went wrong with Lavalink"),
             FerrisError::QueueEmptyError => write!(f, "Nothing is in queue"),
+            FerrisError::HistoryEmptyError => write!(f, "Nothing has been played yet"),
             FerrisError::PositionError => write!(f, "Could not parse position"),
+            FerrisError::QueueIndexError => write!(f, "No such position in queue"),
             FerrisError::AlreadyOnCallError => write!(f, "Already on a voice channel"),
             FerrisError::NotOnCallError => write!(f, "Not on a voice channel"),
             FerrisError::GuildError => write!(f, "Could not get guild infromation"),
             FerrisError::MissingArguments => write!(f, "Missing arguments"),
             FerrisError::TrackNotFoundError => write!(f, "Track could not be found"),
+            FerrisError::VolumeLimitError(max) => write!(f, "Volume can't be over {max}%"),
+            FerrisError::ProfileNotFoundError => write!(f, "Equalizer profile could not be found"),
+            FerrisError::ProfileLimitError => write!(
+                f,
+                "Can't save more than {MAX_EQ_PROFILES} equalizer profiles"
+            ),
+            FerrisError::NotListeningError => {
+                write!(f, "You need to be on the same voice channel as Ferris")
+            }
+            FerrisError::PermissionError(permission) => match permission {
+                CommandPermission::Requester => {
+                    write!(f, "Only DJs can do this to tracks requested by others")
+                }
+                CommandPermission::Admin => {
+                    write!(f, "Only members who can manage the server can do this")
+                }
+                _ => write!(f, "Only DJs can do this"),
+            },
+            FerrisError::UserTrackLimitError(max) => {
+                write!(f, "You can't have more than {max} tracks in queue")
+            }
+            FerrisError::QueueLengthLimitError(max) => {
+                write!(f, "Queue can't have more than {max} tracks")
+            }
+            FerrisError::TrackDurationLimitError(minutes) => {
+                write!(f, "Tracks can't be longer than {minutes} minutes")
+            }
+            FerrisError::StreamLimitError => write!(f, "Live streams are not allowed"),
+            FerrisError::SearchExpiredError => {
+                write!(f, "Search results are no longer available, search again")
+            }
+            FerrisError::SearchOwnerError => {
+                write!(f, "Only the member who searched can pick a result")
+            }
+            FerrisError::TrackLoadError(message) => {
+                write!(f, "Track could not be loaded: {message}")
+            }
+            FerrisError::UnknownSourceError(name) => write!(f, "No such source: {name}"),
         }
     }
 }
 
-/// Looping track type. This is a newtype for lavalinks Track type as we need a seperate type for TypeMap in Node.
-/// This keeps track of which track is looping since we need to requeue it in track end event handler.
-#[derive(Clone, Debug)]
-pub struct LoopingTrack(TrackData);
-
 /// Allows building a response message in embedded Discord message format
 ///
 /// # Examples
@@ -155,6 +264,29 @@ pub fn parse_position(position: &str) -> Duration {
     Duration::from_secs(minutes * 60 + seconds)
 }
 
+/// Parses queue positions from string in format N or N-M
+///
+/// # Arguments
+/// * `range` - single position or inclusive range of positions
+///
+/// # Examples
+/// ```
+/// use dj_ferris::parse_range;
+/// let range = parse_range("2-4");
+///
+/// # assert_eq!(parse_range("2-4"), Some(2..=4));
+/// # assert_eq!(parse_range("3"), Some(3..=3));
+/// # assert_eq!(parse_range(" 1 - 2 "), Some(1..=2));
+/// # assert_eq!(parse_range("4-2"), None);
+/// # assert_eq!(parse_range("a"), None);
+/// ```
+pub fn parse_range(range: &str) -> Option<RangeInclusive<usize>> {
+    let (start, end) = range.split_once('-').unwrap_or((range, range));
+    let start = start.trim().parse::<usize>().ok()?;
+    let end = end.trim().parse::<usize>().ok()?;
+    (start <= end).then_some(start..=end)
+}
+
 pub fn parse_offset_position(
     current_position: Duration,
     sign: char,
@@ -186,18 +318,26 @@ pub async fn get_current_position(ctx: &Context, guild_id: GuildId) -> Option<Du
 /// # Arguments
 /// * `duration` - length of the song as seconds
 /// * `position` - current position in the song as seconds
+/// * `paused` - whether the song is paused
+/// * `speed` - playback speed multiplier, times are shown as they pass in real time
 ///
 ///  # Examples
 /// ```
 /// use dj_ferris::get_progress;
-/// let progress = get_progress(150, 300);
+/// let progress = get_progress(150, 300, false, 1.0);
 ///
-/// assert_eq!(progress, "[▮▮▮▮▮▮▮▮▮▮●▯▯▯▯▯▯▯▯▯]\t02:30/05:00")
+/// assert_eq!(progress, "[▮▮▮▮▮▮▮▮▮▮●▯▯▯▯▯▯▯▯▯]\t02:30/05:00");
+/// # assert_eq!(get_progress(150, 300, true, 1.0), "⏸ [▮▮▮▮▮▮▮▮▮▮●▯▯▯▯▯▯▯▯▯]\t02:30/05:00");
+/// # assert_eq!(get_progress(150, 300, false, 2.0), "[▮▮▮▮▮▮▮▮▮▮●▯▯▯▯▯▯▯▯▯]\t01:15/02:30");
+/// # assert_eq!(get_progress(150, 300, false, 0.5), "[▮▮▮▮▮▮▮▮▮▮●▯▯▯▯▯▯▯▯▯]\t05:00/10:00");
 /// ```
-pub fn get_progress(position: u64, duration: u64) -> String {
+pub fn get_progress(position: u64, duration: u64, paused: bool, speed: f64) -> String {
     let bar_duration = duration / 20;
     let bar_position = position.checked_div(bar_duration).ok_or(0).unwrap_or(0);
     let mut bar: String = String::new();
+    if paused {
+        bar.push_str("⏸ ");
+    }
     bar.push('[');
     for i in 0..20 {
         match i.cmp(&bar_position) {
@@ -207,6 +347,11 @@ pub fn get_progress(position: u64, duration: u64) -> String {
         }
     }
     bar.push_str("]\t");
+    // Convert track time to real time
+    let (position, duration) = (
+        (position as f64 / speed).round() as u64,
+        (duration as f64 / speed).round() as u64,
+    );
     let duration_min = format!("{:02}", duration / 60);
     let duration_sec = format!("{:02}", duration % 60);
     let spot_min = format!("{:02}", position / 60);
@@ -216,6 +361,43 @@ pub fn get_progress(position: u64, duration: u64) -> String {
     bar
 }
 
+/// Format length of a track as minutes and seconds, with hours only when needed
+///
+/// # Examples
+/// ```
+/// use dj_ferris::format_duration;
+/// use std::time::Duration;
+///
+/// assert_eq!(format_duration(Duration::from_secs(150)), "02:30");
+/// assert_eq!(format_duration(Duration::from_secs(3750)), "1:02:30");
+/// ```
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    match seconds / 3600 {
+        0 => format!("{:02}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{hours}:{:02}:{:02}", seconds / 60 % 60, seconds % 60),
+    }
+}
+
+/// Shorten text to at most `max` characters, ending with an ellipsis if it was cut
+///
+/// # Examples
+/// ```
+/// use dj_ferris::truncate;
+///
+/// assert_eq!(truncate("Ferris", 10), "Ferris");
+/// assert_eq!(truncate("Ferris the crab", 10), "Ferris th…");
+/// ```
+pub fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
 pub async fn get_queue(
     lava_client: &LavalinkClient,
     guild_id: GuildId,
@@ -226,3 +408,188 @@ pub async fn get_queue(
         .get_queue();
     Ok(queue)
 }
+
+/// Store the member who requested the track in its user data
+pub fn set_requester(track: &mut TrackData, user_id: UserId) {
+    track.user_data = Some(serde_json::json!({ REQUESTER_KEY: user_id.get() }));
+}
+
+/// Get the member who requested the track from its user data
+///
+/// # Examples
+/// ```
+/// use dj_ferris::{get_requester, set_requester};
+/// use lavalink_rs::model::track::TrackData;
+/// use serenity::model::id::UserId;
+///
+/// let mut track = TrackData::default();
+/// assert_eq!(get_requester(&track), None);
+///
+/// set_requester(&mut track, UserId::new(42));
+/// assert_eq!(get_requester(&track), Some(UserId::new(42)));
+/// ```
+pub fn get_requester(track: &TrackData) -> Option<UserId> {
+    track
+        .user_data
+        .as_ref()?
+        .get(REQUESTER_KEY)?
+        .as_u64()
+        .filter(|id| *id != 0)
+        .map(UserId::new)
+}
+
+/// Get attribution of a track to the member who requested it, or an empty string if no one did
+///
+/// # Examples
+/// ```
+/// use dj_ferris::{requested_by, set_requester};
+/// use lavalink_rs::model::track::TrackData;
+/// use serenity::model::id::UserId;
+///
+/// let mut track = TrackData::default();
+/// assert_eq!(requested_by(&track), "");
+///
+/// set_requester(&mut track, UserId::new(42));
+/// assert_eq!(requested_by(&track), " requested by <@42>");
+/// ```
+pub fn requested_by(track: &TrackData) -> String {
+    get_requester(track)
+        .map(|requester| format!(" requested by {}", requester.mention()))
+        .unwrap_or_default()
+}
+
+/// Get members on the voice channel Ferris is on, or None if Ferris isn't on a voice channel. Bots don't count as listeners.
+///
+/// # Arguments
+///
+/// * `cache` - Discord cache
+/// * `guild_id` - ID of the Discord server
+///
+pub fn get_listeners(cache: &Cache, guild_id: GuildId) -> Option<Vec<UserId>> {
+    let ferris_id = cache.current_user().id;
+    let guild = cache.guild(guild_id)?;
+    let channel_id = guild.voice_states.get(&ferris_id)?.channel_id?;
+    Some(
+        guild
+            .voice_states
+            .values()
+            .filter(|voice_state| {
+                voice_state.channel_id == Some(channel_id)
+                    && voice_state.user_id != ferris_id
+                    && !is_bot(cache, &guild, voice_state)
+            })
+            .map(|voice_state| voice_state.user_id)
+            .collect(),
+    )
+}
+
+/// Whether a voice state belongs to a bot. Voice states cached when Ferris started have no member,
+/// so the member or user is looked up from cache.
+fn is_bot(cache: &Cache, guild: &Guild, voice_state: &VoiceState) -> bool {
+    guild
+        .members
+        .get(&voice_state.user_id)
+        .map(|member| member.user.bot)
+        .or_else(|| voice_state.member.as_ref().map(|member| member.user.bot))
+        .or_else(|| cache.user(voice_state.user_id).map(|user| user.bot))
+        .unwrap_or(false)
+}
+
+/// Get how many votes are needed to skip a track. At least one vote is always needed.
+///
+/// # Arguments
+///
+/// * `listeners` - number of members listening
+/// * `ratio` - share of listeners needed
+///
+/// # Examples
+/// ```
+/// use dj_ferris::votes_needed;
+///
+/// assert_eq!(votes_needed(4, 0.5), 2);
+/// assert_eq!(votes_needed(5, 0.5), 3);
+/// assert_eq!(votes_needed(0, 0.5), 1);
+/// ```
+pub fn votes_needed(listeners: usize, ratio: f64) -> usize {
+    ((listeners as f64 * ratio).ceil() as usize).max(1)
+}
+
+/// Interleave items so that each key takes turns, keeping the order of items with the same key.
+/// Keys take turns in the order they first appear, except `last` which goes last in every round.
+///
+/// # Arguments
+///
+/// * `items` - items to interleave
+/// * `key` - function which gives the key of an item
+/// * `last` - key that had the previous turn
+///
+/// # Examples
+/// ```
+/// use dj_ferris::round_robin;
+///
+/// let tracks = vec![("a", 1), ("a", 2), ("a", 3), ("b", 1), ("c", 1), ("b", 2)];
+/// let fair = round_robin(tracks, |track| track.0, Some("a"));
+/// assert_eq!(fair, vec![("b", 1), ("c", 1), ("a", 1), ("b", 2), ("a", 2), ("a", 3)]);
+/// ```
+pub fn round_robin<T, K: PartialEq>(
+    items: Vec<T>,
+    key: impl Fn(&T) -> K,
+    last: Option<K>,
+) -> Vec<T> {
+    let count = items.len();
+    let mut groups: Vec<(K, VecDeque<T>)> = Vec::new();
+    for item in items {
+        let item_key = key(&item);
+        match groups
+            .iter_mut()
+            .find(|(group_key, _)| *group_key == item_key)
+        {
+            Some((_, group)) => group.push_back(item),
+            None => groups.push((item_key, VecDeque::from([item]))),
+        }
+    }
+    if let Some(last) = last
+        && let Some(index) = groups.iter().position(|(group_key, _)| *group_key == last)
+    {
+        let group = groups.remove(index);
+        groups.push(group);
+    }
+
+    // Take one item from each group per round
+    let mut ordered = Vec::with_capacity(count);
+    while !groups.is_empty() {
+        for (_, group) in groups.iter_mut() {
+            ordered.extend(group.pop_front());
+        }
+        groups.retain(|(_, group)| !group.is_empty());
+    }
+    ordered
+}
+
+/// Find where a new item goes among items interleaved by [`round_robin`] so that keys keep taking turns
+///
+/// # Arguments
+///
+/// * `keys` - keys of the interleaved items in order
+/// * `key` - key of the new item
+/// * `last` - key that had the previous turn
+///
+/// # Examples
+/// ```
+/// use dj_ferris::round_robin_position;
+///
+/// let keys = ["b", "c", "a", "b", "a"];
+/// assert_eq!(round_robin_position(&keys, "c", Some("a")), 4);
+/// assert_eq!(round_robin_position(&keys, "d", Some("a")), 2);
+/// ```
+pub fn round_robin_position<K: Clone + PartialEq>(keys: &[K], key: K, last: Option<K>) -> usize {
+    let new = keys.len();
+    round_robin(
+        (0..=new).collect(),
+        |index| keys.get(*index).cloned().unwrap_or_else(|| key.clone()),
+        last,
+    )
+    .iter()
+    .position(|index| *index == new)
+    .unwrap_or(new)
+}
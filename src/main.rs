@@ -1,6 +1,6 @@
 use dj_ferris::{
-    Lavalink, LoopingTrack,
-    events::{Handler, track_start},
+    Lavalink, LavalinkData,
+    events::{Handler, track_end, track_exception, track_start, track_stuck, websocket_closed},
 };
 use lavalink_rs::{
     client::LavalinkClient,
@@ -10,9 +10,8 @@ use lavalink_rs::{
 #[allow(deprecated)]
 use serenity::framework::StandardFramework;
 use serenity::{client::Client, prelude::GatewayIntents};
-use songbird::{Config, SerenityInit};
+use songbird::{Config, SerenityInit, Songbird};
 use std::{env, sync::Arc};
-use tokio::sync::RwLock;
 use tracing::{Level, log::error};
 use tracing_subscriber::EnvFilter;
 
@@ -41,7 +40,7 @@ async fn main() {
     });
     #[allow(deprecated)]
     let framework = StandardFramework::new();
-    let config = Config::default();
+    let songbird = Songbird::serenity_from_config(Config::default());
     let application_id = dotenvy::var("APPLICATION_ID")
         .unwrap_or_else(|_| {
             env::var("APPLICATION_ID")
@@ -53,7 +52,7 @@ async fn main() {
         .event_handler(Handler)
         .application_id(application_id)
         .framework(framework)
-        .register_songbird_from_config(config)
+        .register_songbird_with(songbird.clone())
         .await
         .expect("Error creating serenity client");
 
@@ -75,15 +74,24 @@ async fn main() {
     };
     let events = events::Events {
         track_start: Some(track_start),
+        track_end: Some(track_end),
+        track_exception: Some(track_exception),
+        track_stuck: Some(track_stuck),
+        websocket_closed: Some(websocket_closed),
         ..Default::default()
     };
 
-    let looping: Option<LoopingTrack> = None;
     let lava_client = LavalinkClient::new_with_data(
         events,
         vec![nodes],
         NodeDistributionStrategy::default(),
-        Arc::new(RwLock::new(looping)),
+        Arc::new(LavalinkData {
+            sessions: Default::default(),
+            http: client.http.clone(),
+            cache: client.cache.clone(),
+            songbird,
+            suggestions: Default::default(),
+        }),
     )
     .await;
 
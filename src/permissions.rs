@@ -0,0 +1,93 @@
+use crate::{
+    FerrisError, Lavalink,
+    session::{GuildSettings, get_settings},
+};
+use serenity::{
+    all::{CommandInteraction, Permissions},
+    client::Context,
+};
+
+/// Who can use a command
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommandPermission {
+    /// Anyone
+    Listener,
+    /// Anyone for tracks they requested, DJs for all tracks. Commands check track ownership themselves.
+    Requester,
+    /// Members with the DJ role, or anyone if the guild has no DJ role
+    Dj,
+    /// Members who can manage the server
+    Admin,
+}
+
+impl CommandPermission {
+    /// Get who can use a command by its name
+    ///
+    /// # Examples
+    /// ```
+    /// use dj_ferris::permissions::CommandPermission;
+    ///
+    /// assert_eq!(CommandPermission::of_command("play"), CommandPermission::Listener);
+    /// assert_eq!(CommandPermission::of_command("stop"), CommandPermission::Dj);
+    /// assert_eq!(CommandPermission::of_command("settings"), CommandPermission::Admin);
+    /// ```
+    pub fn of_command(name: &str) -> Self {
+        match name {
+            "settings" | "announce" | "247" => CommandPermission::Admin,
+            "skip" | "remove" => CommandPermission::Requester,
+            "help" | "join" | "play" | "search" | "queue" | "np" | "history" => {
+                CommandPermission::Listener
+            }
+            _ => CommandPermission::Dj,
+        }
+    }
+}
+
+/// Whether the member can manage the server
+pub fn is_admin(interaction: &CommandInteraction) -> bool {
+    interaction
+        .member
+        .as_ref()
+        .and_then(|member| member.permissions)
+        .is_some_and(|permissions| permissions.contains(Permissions::MANAGE_GUILD))
+}
+
+/// Whether the member has the DJ role of the guild. Admins are always DJs.
+pub fn is_dj(settings: &GuildSettings, interaction: &CommandInteraction) -> bool {
+    is_admin(interaction)
+        || settings.dj_role.is_some_and(|role_id| {
+            interaction
+                .member
+                .as_ref()
+                .is_some_and(|member| member.roles.contains(&role_id))
+        })
+}
+
+/// Whether the member can use commands meant for DJs, which is anyone when the guild has no DJ role
+pub fn has_dj_access(settings: &GuildSettings, interaction: &CommandInteraction) -> bool {
+    settings.dj_role.is_none() || is_dj(settings, interaction)
+}
+
+/// Check that the member is allowed to use the command before running it
+pub async fn check_permission(
+    ctx: &Context,
+    interaction: &CommandInteraction,
+) -> Result<(), FerrisError> {
+    let permission = CommandPermission::of_command(&interaction.data.name);
+    let allowed = match permission {
+        CommandPermission::Listener | CommandPermission::Requester => true,
+        CommandPermission::Admin => is_admin(interaction),
+        CommandPermission::Dj => {
+            let guild_id = interaction.guild_id.ok_or(FerrisError::GuildError)?;
+            let data = ctx.data.read().await;
+            let lava_client = data.get::<Lavalink>().ok_or(FerrisError::LavalinkError)?;
+            has_dj_access(&get_settings(lava_client, guild_id).await?, interaction)
+        }
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(FerrisError::PermissionError(permission))
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{
+    DEFAULT_IDLE_TIMEOUT, DEFAULT_VOLUME, DEFAULT_VOTE_SKIP_RATIO, FerrisError, LavalinkData,
+    MAX_HISTORY, MAX_VOLUME,
+    filters::{EqualizerBands, FilterPreset, build_filters},
+    sources::{DEFAULT_FALLBACK_SOURCES, SearchSource},
+};
+use lavalink_rs::client::LavalinkClient;
+use lavalink_rs::model::{player::Filters, track::TrackData};
+use serenity::{
+    all::Mentionable,
+    model::id::{ChannelId, GuildId, InteractionId, RoleId, UserId},
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::Display,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::sync::RwLock;
+
+/// Session state of every guild Ferris is in, keyed by guild. This is stored in data of the Lavalink client.
+pub type Sessions = RwLock<HashMap<GuildId, Session>>;
+
+/// Player state of a single guild
+#[derive(Clone, Debug, Default)]
+pub struct Session {
+    /// What is being looped
+    pub loop_mode: LoopMode,
+    /// Whether new tracks are added to random positions in queue
+    pub shuffle: bool,
+    /// Text channel where the session was started from
+    pub text_channel: Option<ChannelId>,
+    /// Member who started the session
+    pub started_by: Option<UserId>,
+    /// Active filter presets in the order they were added
+    pub filters: Vec<FilterPreset>,
+    /// Custom equalizer gains
+    pub equalizer: EqualizerBands,
+    /// Custom speed multiplier
+    pub speed: Option<f64>,
+    /// Custom pitch multiplier
+    pub pitch: Option<f64>,
+    /// Finished tracks, newest last
+    pub history: VecDeque<TrackData>,
+    /// Whether the track that ends next was left by going back in history and shouldn't be recorded
+    pub going_back: bool,
+    /// When Ferris was left alone or stopped playing, None while in use
+    pub idle_since: Option<Instant>,
+    /// Number of the latest idle timer, older timers stop when they see a newer one. Kept when the session ends.
+    pub idle_timer: u64,
+    /// Members who have voted to skip the current track
+    pub skip_votes: HashSet<UserId>,
+    /// Whether votes have already skipped the current track
+    pub vote_skipped: bool,
+    /// Search results waiting to be picked, keyed by the search interaction
+    pub searches: HashMap<InteractionId, PendingSearch>,
+    /// Settings of the guild. These are kept when the session ends.
+    pub settings: GuildSettings,
+}
+
+/// Results of a search waiting for the member who searched to pick one
+#[derive(Clone, Debug)]
+pub struct PendingSearch {
+    /// Member who searched
+    pub user_id: UserId,
+    /// When the search was made
+    pub searched_at: Instant,
+    pub results: Vec<TrackData>,
+}
+
+impl Session {
+    /// End the session when Ferris leaves the voice channel. Only settings and the idle timer number are kept.
+    pub fn end(&mut self) {
+        *self = Session {
+            idle_timer: self.idle_timer,
+            settings: self.settings.clone(),
+            ..Default::default()
+        };
+    }
+
+    /// Record a finished track to history, forgetting the oldest tracks when history is full
+    pub fn record_history(&mut self, track: TrackData) {
+        if std::mem::take(&mut self.going_back)
+            || self
+                .history
+                .back()
+                .is_some_and(|last| last.encoded == track.encoded)
+        {
+            return;
+        }
+        if self.history.len() >= MAX_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(track);
+    }
+
+    /// Lavalink filters made from custom filters and filter presets
+    pub fn lavalink_filters(&self) -> Filters {
+        build_filters(&self.filters, &self.equalizer, self.speed, self.pitch)
+    }
+
+    /// Text channel where messages that aren't replies to commands are posted, if any
+    pub fn announce_channel(&self) -> Option<ChannelId> {
+        match self.settings.announcements {
+            Announcements::SessionChannel => self.text_channel,
+            Announcements::Channel(channel_id) => Some(channel_id),
+            Announcements::Off => None,
+        }
+    }
+}
+
+/// Settings of a guild, configured by admins
+#[derive(Clone, Debug)]
+pub struct GuildSettings {
+    /// Volume the player starts with
+    pub default_volume: u16,
+    /// Highest volume members can set
+    pub max_volume: u16,
+    /// Saved equalizer profiles by name
+    pub eq_profiles: BTreeMap<String, EqualizerBands>,
+    /// Where announcements are posted
+    pub announcements: Announcements,
+    /// How long Ferris stays when alone or idle
+    pub idle_timeout: Duration,
+    /// Whether Ferris stays on the voice channel even when alone or idle
+    pub always_on: bool,
+    /// Whether skipping needs votes from listeners
+    pub vote_skip: bool,
+    /// Share of listeners needed to skip a track when vote skipping
+    pub vote_skip_ratio: f64,
+    /// Role allowed to control the player, None lets everyone control it
+    pub dj_role: Option<RoleId>,
+    /// Whether requesters take turns in queue
+    pub fair_queue: bool,
+    /// Most tracks a single member can have in queue
+    pub max_user_tracks: Option<usize>,
+    /// Most tracks in queue
+    pub max_queue_length: Option<usize>,
+    /// Longest track in minutes that can be queued
+    pub max_track_minutes: Option<u64>,
+    /// Whether live streams can be queued
+    pub allow_streams: bool,
+    /// Where queries are searched when no source is chosen
+    pub default_source: SearchSource,
+    /// Sources tried in order when the chosen source finds nothing
+    pub fallback_sources: Vec<SearchSource>,
+}
+
+impl Default for GuildSettings {
+    fn default() -> Self {
+        Self {
+            default_volume: DEFAULT_VOLUME,
+            max_volume: MAX_VOLUME,
+            eq_profiles: BTreeMap::new(),
+            announcements: Announcements::default(),
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            always_on: false,
+            vote_skip: false,
+            vote_skip_ratio: DEFAULT_VOTE_SKIP_RATIO,
+            dj_role: None,
+            fair_queue: false,
+            max_user_tracks: None,
+            max_queue_length: None,
+            max_track_minutes: None,
+            allow_streams: true,
+            default_source: SearchSource::default(),
+            fallback_sources: DEFAULT_FALLBACK_SOURCES.to_vec(),
+        }
+    }
+}
+
+/// Where a guild wants announcements like now playing messages
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Announcements {
+    /// Text channel the session was started from
+    #[default]
+    SessionChannel,
+    /// Fixed text channel
+    Channel(ChannelId),
+    Off,
+}
+
+impl Display for Announcements {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Announcements::SessionChannel => {
+                write!(f, "Announcing in the channel Ferris was summoned from")
+            }
+            Announcements::Channel(channel_id) => {
+                write!(f, "Announcing in {}", channel_id.mention())
+            }
+            Announcements::Off => write!(f, "Not announcing"),
+        }
+    }
+}
+
+/// Looping mode of a guild.
+///
+/// While looping, a copy of the current track is requeued in track start event handler.
+/// Track mode puts the copy to the front of the queue and queue mode to the end of the queue.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode {
+    #[default]
+    Off,
+    Track,
+    Queue,
+}
+
+impl Display for LoopMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            LoopMode::Off => write!(f, "Not looping"),
+            LoopMode::Track => write!(f, "🔂 Looping track"),
+            LoopMode::Queue => write!(f, "🔁 Looping queue"),
+        }
+    }
+}
+
+/// Get session state of all guilds from Lavalink client
+pub fn get_sessions(lava_client: &LavalinkClient) -> Result<Arc<Sessions>, FerrisError> {
+    Ok(lava_client
+        .data::<LavalinkData>()
+        .map_err(|_| FerrisError::LavalinkError)?
+        .sessions
+        .clone())
+}
+
+/// Get settings of a guild, or default settings if the guild has none yet
+pub async fn get_settings(
+    lava_client: &LavalinkClient,
+    guild_id: GuildId,
+) -> Result<GuildSettings, FerrisError> {
+    Ok(get_sessions(lava_client)?
+        .read()
+        .await
+        .get(&guild_id)
+        .map(|session| session.settings.clone())
+        .unwrap_or_default())
+}
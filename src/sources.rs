@@ -0,0 +1,180 @@
+use lavalink_rs::prelude::SearchEngines;
+use std::fmt::Display;
+
+/// Sources Ferris can search tracks from. Sources other than YouTube, YouTube Music and SoundCloud
+/// need the LavaSrc plugin on the Lavalink server.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SearchSource {
+    #[default]
+    YouTube,
+    YouTubeMusic,
+    SoundCloud,
+    Spotify,
+    AppleMusic,
+    Deezer,
+    YandexMusic,
+}
+
+impl SearchSource {
+    pub const ALL: [SearchSource; 7] = [
+        SearchSource::YouTube,
+        SearchSource::YouTubeMusic,
+        SearchSource::SoundCloud,
+        SearchSource::Spotify,
+        SearchSource::AppleMusic,
+        SearchSource::Deezer,
+        SearchSource::YandexMusic,
+    ];
+
+    /// Name of the source used in commands
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchSource::YouTube => "youtube",
+            SearchSource::YouTubeMusic => "youtubemusic",
+            SearchSource::SoundCloud => "soundcloud",
+            SearchSource::Spotify => "spotify",
+            SearchSource::AppleMusic => "applemusic",
+            SearchSource::Deezer => "deezer",
+            SearchSource::YandexMusic => "yandexmusic",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.name() == name)
+    }
+
+    /// Prefix which picks the source inline in a query, like `sc:` in `sc:never gonna give you up`
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            SearchSource::YouTube => "yt",
+            SearchSource::YouTubeMusic => "ytm",
+            SearchSource::SoundCloud => "sc",
+            SearchSource::Spotify => "sp",
+            SearchSource::AppleMusic => "am",
+            SearchSource::Deezer => "dz",
+            SearchSource::YandexMusic => "ym",
+        }
+    }
+
+    /// Lavalink search engine of the source
+    pub fn engine(&self) -> SearchEngines {
+        match self {
+            SearchSource::YouTube => SearchEngines::YouTube,
+            SearchSource::YouTubeMusic => SearchEngines::YouTubeMusic,
+            SearchSource::SoundCloud => SearchEngines::SoundCloud,
+            SearchSource::Spotify => SearchEngines::Spotify,
+            SearchSource::AppleMusic => SearchEngines::AppleMusic,
+            SearchSource::Deezer => SearchEngines::Deezer,
+            SearchSource::YandexMusic => SearchEngines::YandexMusic,
+        }
+    }
+}
+
+impl Display for SearchSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SearchSource::YouTube => write!(f, "YouTube"),
+            SearchSource::YouTubeMusic => write!(f, "YouTube Music"),
+            SearchSource::SoundCloud => write!(f, "SoundCloud"),
+            SearchSource::Spotify => write!(f, "Spotify"),
+            SearchSource::AppleMusic => write!(f, "Apple Music"),
+            SearchSource::Deezer => write!(f, "Deezer"),
+            SearchSource::YandexMusic => write!(f, "Yandex Music"),
+        }
+    }
+}
+
+/// Split a source prefix like `sc:` from the start of a query
+///
+/// # Examples
+/// ```
+/// use dj_ferris::sources::{SearchSource, split_prefix};
+///
+/// assert_eq!(split_prefix("sc:lofi beats"), (Some(SearchSource::SoundCloud), "lofi beats"));
+/// assert_eq!(split_prefix("ytm: lofi beats"), (Some(SearchSource::YouTubeMusic), "lofi beats"));
+/// assert_eq!(split_prefix("lofi: beats"), (None, "lofi: beats"));
+/// ```
+pub fn split_prefix(query: &str) -> (Option<SearchSource>, &str) {
+    query
+        .split_once(':')
+        .and_then(|(prefix, rest)| {
+            SearchSource::ALL
+                .into_iter()
+                .find(|source| source.prefix() == prefix.trim().to_lowercase())
+                .map(|source| (Some(source), rest.trim()))
+        })
+        .unwrap_or((None, query))
+}
+
+/// Pick where to search for a query. Prefix in the query goes first, then the source chosen in the command
+/// and finally the default source of the guild.
+///
+/// # Arguments
+///
+/// * `query` - search query, possibly with a prefix
+/// * `chosen` - source chosen in command options
+/// * `default` - default source of the guild
+///
+pub fn pick_source(
+    query: &str,
+    chosen: Option<SearchSource>,
+    default: SearchSource,
+) -> (SearchSource, &str) {
+    let (prefixed, query) = split_prefix(query);
+    (prefixed.or(chosen).unwrap_or(default), query)
+}
+
+/// Sources tried after the chosen one when it finds nothing, these work without plugins
+pub const DEFAULT_FALLBACK_SOURCES: [SearchSource; 2] =
+    [SearchSource::YouTubeMusic, SearchSource::SoundCloud];
+
+/// Parse a list of sources separated by commas or spaces. Sources can be given by their name or prefix
+/// and `none` gives an empty list. Returns the first unknown source as an error.
+///
+/// # Examples
+/// ```
+/// use dj_ferris::sources::{SearchSource, parse_sources};
+///
+/// assert_eq!(
+///     parse_sources("youtubemusic, sc"),
+///     Ok(vec![SearchSource::YouTubeMusic, SearchSource::SoundCloud])
+/// );
+/// assert_eq!(parse_sources("none"), Ok(Vec::new()));
+/// assert_eq!(parse_sources("sc, vimeo"), Err("vimeo"));
+/// ```
+pub fn parse_sources(text: &str) -> Result<Vec<SearchSource>, &str> {
+    if text.trim().eq_ignore_ascii_case("none") {
+        return Ok(Vec::new());
+    }
+    let mut sources = Vec::new();
+    for name in text.split([',', ' ']).filter(|name| !name.is_empty()) {
+        let source = SearchSource::ALL
+            .into_iter()
+            .find(|source| {
+                source.name().eq_ignore_ascii_case(name)
+                    || source.prefix().eq_ignore_ascii_case(name)
+            })
+            .ok_or(name)?;
+        if !sources.contains(&source) {
+            sources.push(source);
+        }
+    }
+    Ok(sources)
+}
+
+/// Order of sources to search from, the first source followed by fallbacks that aren't the first
+///
+/// # Examples
+/// ```
+/// use dj_ferris::sources::{SearchSource, search_order};
+///
+/// assert_eq!(
+///     search_order(SearchSource::SoundCloud, &[SearchSource::YouTubeMusic, SearchSource::SoundCloud]),
+///     vec![SearchSource::SoundCloud, SearchSource::YouTubeMusic]
+/// );
+/// ```
+pub fn search_order(first: SearchSource, fallbacks: &[SearchSource]) -> Vec<SearchSource> {
+    std::iter::once(first)
+        .chain(fallbacks.iter().copied().filter(|source| *source != first))
+        .collect()
+}